@@ -0,0 +1,705 @@
+use crate::fen::FenError;
+use crate::types::{CastlingSide, Color, Disambiguation, File, Piece, Rank, Square};
+use crate::{ChessGame, ChessMove, ChessTurn, MoveNotation};
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CastlingRights {
+    pub white_kingside: bool,
+    pub white_queenside: bool,
+    pub black_kingside: bool,
+    pub black_queenside: bool,
+}
+
+impl CastlingRights {
+    fn kingside(&self, color: Color) -> bool {
+        match color {
+            Color::White => self.white_kingside,
+            Color::Black => self.black_kingside,
+        }
+    }
+
+    fn queenside(&self, color: Color) -> bool {
+        match color {
+            Color::White => self.white_queenside,
+            Color::Black => self.black_queenside,
+        }
+    }
+
+    fn remove(&mut self, color: Color, kingside: bool) {
+        match (color, kingside) {
+            (Color::White, true) => self.white_kingside = false,
+            (Color::White, false) => self.white_queenside = false,
+            (Color::Black, true) => self.black_kingside = false,
+            (Color::Black, false) => self.black_queenside = false,
+        }
+    }
+}
+
+// a move resolved on the board
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlayedMove {
+    pub color: Color,
+    pub piece: Piece,
+    pub from: Square,
+    pub to: Square,
+    pub captured: Option<Piece>,
+    pub promotion: Option<Piece>,
+    pub castling: bool,
+    pub en_passant: bool,
+    pub null: bool, // passes the turn, from and to are the king's square
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReplayErrorKind {
+    Illegal,
+    Ambiguous,
+    OutOfTurn,            // move recorded for the side that isn't to move
+    InvalidFen(FenError), // the FEN tag of the game can't be set up
+}
+
+// move of a game that couldn't be replayed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReplayError {
+    pub turn_number: usize,
+    pub color: Color,
+    pub move_text: String,
+    pub kind: ReplayErrorKind,
+    pub line: usize, // position of the move in the source, 0 when unknown
+    pub column: usize,
+}
+
+impl ReplayError {
+    pub(crate) fn at_move(
+        turn_number: usize,
+        color: Color,
+        mv: &ChessMove,
+        kind: ReplayErrorKind,
+    ) -> ReplayError {
+        ReplayError {
+            turn_number,
+            color,
+            move_text: mv.full_str.clone(),
+            kind,
+            line: mv.line,
+            column: mv.column,
+        }
+    }
+}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let problem = match &self.kind {
+            ReplayErrorKind::Illegal => "illegal move",
+            ReplayErrorKind::Ambiguous => "ambiguous move",
+            ReplayErrorKind::OutOfTurn => "move out of turn",
+            ReplayErrorKind::InvalidFen(error) => {
+                return write!(f, "invalid FEN tag '{}': {}", self.move_text, error)
+            }
+        };
+        write!(
+            f,
+            "{} '{}' in turn {} ({})",
+            problem, self.move_text, self.turn_number, self.color
+        )
+    }
+}
+
+impl std::error::Error for ReplayError {}
+
+const KNIGHT_STEPS: [(i8, i8); 8] = [
+    (1, 2),
+    (2, 1),
+    (2, -1),
+    (1, -2),
+    (-1, -2),
+    (-2, -1),
+    (-2, 1),
+    (-1, 2),
+];
+const KING_STEPS: [(i8, i8); 8] = [
+    (1, 0),
+    (1, 1),
+    (0, 1),
+    (-1, 1),
+    (-1, 0),
+    (-1, -1),
+    (0, -1),
+    (1, -1),
+];
+const ROOK_DIRECTIONS: [(i8, i8); 4] = [(1, 0), (0, 1), (-1, 0), (0, -1)];
+const BISHOP_DIRECTIONS: [(i8, i8); 4] = [(1, 1), (-1, 1), (-1, -1), (1, -1)];
+const PROMOTION_PIECES: [Piece; 4] = [Piece::Queen, Piece::Rook, Piece::Bishop, Piece::Knight];
+
+// square from 0-based indices known to be on the board
+fn at(file: u8, rank: u8) -> Square {
+    Square::new(File(file), Rank(rank))
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Board {
+    squares: [[Option<(Color, Piece)>; 8]; 8], // indexed by rank, then file
+    pub side_to_move: Color,
+    pub castling: CastlingRights,
+    pub en_passant: Option<Square>, // square a pawn can be captured on en passant
+    pub halfmove_clock: u32,
+    pub fullmove_number: u32,
+}
+
+// the standard starting position
+impl Default for Board {
+    fn default() -> Self {
+        let mut board = Board::empty();
+        let back_rank = [
+            Piece::Rook,
+            Piece::Knight,
+            Piece::Bishop,
+            Piece::Queen,
+            Piece::King,
+            Piece::Bishop,
+            Piece::Knight,
+            Piece::Rook,
+        ];
+        for (file, piece) in back_rank.into_iter().enumerate() {
+            board.squares[0][file] = Some((Color::White, piece));
+            board.squares[1][file] = Some((Color::White, Piece::Pawn));
+            board.squares[6][file] = Some((Color::Black, Piece::Pawn));
+            board.squares[7][file] = Some((Color::Black, piece));
+        }
+        board.castling = CastlingRights {
+            white_kingside: true,
+            white_queenside: true,
+            black_kingside: true,
+            black_queenside: true,
+        };
+        board
+    }
+}
+
+impl Board {
+    // board without any pieces, white to move
+    pub fn empty() -> Self {
+        Board {
+            squares: [[None; 8]; 8],
+            side_to_move: Color::White,
+            castling: CastlingRights {
+                white_kingside: false,
+                white_queenside: false,
+                black_kingside: false,
+                black_queenside: false,
+            },
+            en_passant: None,
+            halfmove_clock: 0,
+            fullmove_number: 1,
+        }
+    }
+
+    pub fn piece_at(&self, square: Square) -> Option<(Color, Piece)> {
+        self.squares[square.rank.index() as usize][square.file.index() as usize]
+    }
+
+    pub fn set_piece(&mut self, square: Square, piece: Option<(Color, Piece)>) {
+        self.squares[square.rank.index() as usize][square.file.index() as usize] = piece;
+    }
+
+    fn king_square(&self, color: Color) -> Option<Square> {
+        Square::all().find(|&square| self.piece_at(square) == Some((color, Piece::King)))
+    }
+
+    // whether any piece of `by` attacks `square`
+    pub fn is_attacked(&self, square: Square, by: Color) -> bool {
+        let has = |target: Option<Square>, pieces: &[Piece]| {
+            target
+                .and_then(|target| self.piece_at(target))
+                .is_some_and(|(color, piece)| color == by && pieces.contains(&piece))
+        };
+
+        // a pawn attacks diagonally forward, so look diagonally backwards from the square
+        let pawn_rank = -by.forward();
+        if has(square.offset(-1, pawn_rank), &[Piece::Pawn])
+            || has(square.offset(1, pawn_rank), &[Piece::Pawn])
+        {
+            return true;
+        }
+        if KNIGHT_STEPS
+            .iter()
+            .any(|&(df, dr)| has(square.offset(df, dr), &[Piece::Knight]))
+        {
+            return true;
+        }
+        if KING_STEPS
+            .iter()
+            .any(|&(df, dr)| has(square.offset(df, dr), &[Piece::King]))
+        {
+            return true;
+        }
+
+        let slider_attacks = |directions: &[(i8, i8)], pieces: &[Piece]| {
+            directions.iter().any(|&(df, dr)| {
+                let mut current = square.offset(df, dr);
+                while let Some(target) = current {
+                    if let Some(piece) = self.piece_at(target) {
+                        return piece.0 == by && pieces.contains(&piece.1);
+                    }
+                    current = target.offset(df, dr);
+                }
+                false
+            })
+        };
+        slider_attacks(&ROOK_DIRECTIONS, &[Piece::Rook, Piece::Queen])
+            || slider_attacks(&BISHOP_DIRECTIONS, &[Piece::Bishop, Piece::Queen])
+    }
+
+    // whether the side to move is in check
+    pub fn is_check(&self) -> bool {
+        self.king_square(self.side_to_move)
+            .is_some_and(|king| self.is_attacked(king, self.side_to_move.opponent()))
+    }
+
+    pub fn is_checkmate(&self) -> bool {
+        self.is_check() && self.legal_moves().is_empty()
+    }
+
+    pub fn is_stalemate(&self) -> bool {
+        !self.is_check() && self.legal_moves().is_empty()
+    }
+
+    // passing the turn, which isn't allowed in check
+    pub fn null_move(&self) -> Option<PlayedMove> {
+        if self.is_check() {
+            return None;
+        }
+        let king = self.king_square(self.side_to_move)?;
+        Some(PlayedMove {
+            color: self.side_to_move,
+            piece: Piece::King,
+            from: king,
+            to: king,
+            captured: None,
+            promotion: None,
+            castling: false,
+            en_passant: false,
+            null: true,
+        })
+    }
+
+    fn push_pawn_moves(
+        &self,
+        from: Square,
+        to: Square,
+        captured: Option<Piece>,
+        moves: &mut Vec<PlayedMove>,
+    ) {
+        let color = self.side_to_move;
+        let mv = PlayedMove {
+            color,
+            piece: Piece::Pawn,
+            from,
+            to,
+            captured,
+            promotion: None,
+            castling: false,
+            en_passant: false,
+            null: false,
+        };
+
+        if to.rank.index() == color.opponent().back_rank() {
+            for piece in PROMOTION_PIECES {
+                moves.push(PlayedMove {
+                    promotion: Some(piece),
+                    ..mv.clone()
+                });
+            }
+        } else {
+            moves.push(mv);
+        }
+    }
+
+    fn pseudo_legal_moves(&self) -> Vec<PlayedMove> {
+        let color = self.side_to_move;
+        let mut moves = Vec::new();
+
+        for from in Square::all() {
+            let piece = match self.piece_at(from) {
+                Some((piece_color, piece)) if piece_color == color => piece,
+                _ => continue,
+            };
+
+            let target_move = |to: Square, captured: Option<Piece>| PlayedMove {
+                color,
+                piece,
+                from,
+                to,
+                captured,
+                promotion: None,
+                castling: false,
+                en_passant: false,
+                null: false,
+            };
+
+            match piece {
+                Piece::Pawn => {
+                    let forward = color.forward();
+                    if let Some(one) = from.offset(0, forward) {
+                        if self.piece_at(one).is_none() {
+                            self.push_pawn_moves(from, one, None, &mut moves);
+
+                            let start_rank = (color.back_rank() as i8 + forward) as u8;
+                            if let Some(two) = one.offset(0, forward) {
+                                if from.rank.index() == start_rank && self.piece_at(two).is_none() {
+                                    moves.push(target_move(two, None));
+                                }
+                            }
+                        }
+                    }
+
+                    for file_step in [-1, 1] {
+                        let Some(to) = from.offset(file_step, forward) else {
+                            continue;
+                        };
+                        match self.piece_at(to) {
+                            Some((target_color, target)) if target_color != color => {
+                                self.push_pawn_moves(from, to, Some(target), &mut moves);
+                            }
+                            None if self.en_passant == Some(to) => moves.push(PlayedMove {
+                                en_passant: true,
+                                ..target_move(to, Some(Piece::Pawn))
+                            }),
+                            _ => {}
+                        }
+                    }
+                }
+                Piece::Knight | Piece::King => {
+                    let steps = if piece == Piece::Knight {
+                        KNIGHT_STEPS
+                    } else {
+                        KING_STEPS
+                    };
+                    for (df, dr) in steps {
+                        let Some(to) = from.offset(df, dr) else {
+                            continue;
+                        };
+                        match self.piece_at(to) {
+                            Some((target_color, _)) if target_color == color => {}
+                            target => moves.push(target_move(to, target.map(|t| t.1))),
+                        }
+                    }
+                }
+                _ => {
+                    let directions: Vec<(i8, i8)> = match piece {
+                        Piece::Rook => ROOK_DIRECTIONS.to_vec(),
+                        Piece::Bishop => BISHOP_DIRECTIONS.to_vec(),
+                        _ => [ROOK_DIRECTIONS, BISHOP_DIRECTIONS].concat(),
+                    };
+                    for (df, dr) in directions {
+                        let mut current = from.offset(df, dr);
+                        while let Some(to) = current {
+                            match self.piece_at(to) {
+                                None => moves.push(target_move(to, None)),
+                                Some((target_color, target)) => {
+                                    if target_color != color {
+                                        moves.push(target_move(to, Some(target)));
+                                    }
+                                    break;
+                                }
+                            }
+                            current = to.offset(df, dr);
+                        }
+                    }
+                }
+            }
+        }
+
+        moves.extend(self.castling_moves());
+        moves
+    }
+
+    fn castling_moves(&self) -> Vec<PlayedMove> {
+        let color = self.side_to_move;
+        let rank = color.back_rank();
+        let king_from = at(4, rank);
+        let mut moves = Vec::new();
+
+        if self.piece_at(king_from) != Some((color, Piece::King)) || self.is_check() {
+            return moves;
+        }
+
+        // (allowed, rook file, files that must be empty, files the king passes through)
+        let sides = [
+            (self.castling.kingside(color), 7, vec![5, 6], [5, 6]),
+            (self.castling.queenside(color), 0, vec![1, 2, 3], [3, 2]),
+        ];
+        for (allowed, rook_file, empty_files, king_path) in sides {
+            let rook_ok = self.piece_at(at(rook_file, rank)) == Some((color, Piece::Rook));
+            let path_empty = empty_files
+                .iter()
+                .all(|&file| self.piece_at(at(file, rank)).is_none());
+            let path_safe = king_path
+                .iter()
+                .all(|&file| !self.is_attacked(at(file, rank), color.opponent()));
+
+            if allowed && rook_ok && path_empty && path_safe {
+                moves.push(PlayedMove {
+                    color,
+                    piece: Piece::King,
+                    from: king_from,
+                    to: at(king_path[1], rank),
+                    captured: None,
+                    promotion: None,
+                    castling: true,
+                    en_passant: false,
+                    null: false,
+                });
+            }
+        }
+
+        moves
+    }
+
+    // every legal move of the side to move
+    pub fn legal_moves(&self) -> Vec<PlayedMove> {
+        self.pseudo_legal_moves()
+            .into_iter()
+            .filter(|mv| {
+                let mut board = self.clone();
+                board.make_move(mv);
+                !board
+                    .king_square(mv.color)
+                    .is_some_and(|king| board.is_attacked(king, mv.color.opponent()))
+            })
+            .collect()
+    }
+
+    // plays a move, which must be one of `legal_moves` or the null move
+    pub fn make_move(&mut self, mv: &PlayedMove) {
+        let color = mv.color;
+        // a null move only hands the turn over
+        if !mv.null {
+            self.move_pieces(mv);
+        }
+
+        self.en_passant = None;
+        if mv.piece == Piece::Pawn && mv.from.rank.index().abs_diff(mv.to.rank.index()) == 2 {
+            self.en_passant = mv.from.offset(0, color.forward());
+        }
+
+        if mv.piece == Piece::Pawn || mv.captured.is_some() {
+            self.halfmove_clock = 0;
+        } else {
+            self.halfmove_clock += 1;
+        }
+        if color == Color::Black {
+            self.fullmove_number += 1;
+        }
+        self.side_to_move = color.opponent();
+    }
+
+    fn move_pieces(&mut self, mv: &PlayedMove) {
+        let color = mv.color;
+
+        self.set_piece(mv.from, None);
+        self.set_piece(mv.to, Some((color, mv.promotion.unwrap_or(mv.piece))));
+
+        if mv.en_passant {
+            self.set_piece(Square::new(mv.to.file, mv.from.rank), None);
+        }
+        if mv.castling {
+            let (rook_from, rook_to) = if mv.to.file.index() == 6 {
+                (7, 5)
+            } else {
+                (0, 3)
+            };
+            let rank = mv.from.rank.index();
+            self.set_piece(at(rook_from, rank), None);
+            self.set_piece(at(rook_to, rank), Some((color, Piece::Rook)));
+        }
+
+        // moving the king or a rook, or capturing a rook, loses the matching castling rights
+        if mv.piece == Piece::King {
+            self.castling.remove(color, true);
+            self.castling.remove(color, false);
+        }
+        for side in [Color::White, Color::Black] {
+            for (file, kingside) in [(7, true), (0, false)] {
+                let corner = at(file, side.back_rank());
+                if mv.from == corner || mv.to == corner {
+                    self.castling.remove(side, kingside);
+                }
+            }
+        }
+    }
+
+    // finds the legal move a parsed move stands for
+    pub fn resolve_move(&self, mv: &ChessMove) -> Result<PlayedMove, ReplayErrorKind> {
+        if mv.null_move {
+            return self.null_move().ok_or(ReplayErrorKind::Illegal);
+        }
+        let mut candidates: Vec<PlayedMove> = self
+            .legal_moves()
+            .into_iter()
+            .filter(|candidate| matches_move(candidate, mv))
+            .collect();
+
+        match candidates.len() {
+            0 => Err(ReplayErrorKind::Illegal),
+            1 => Ok(candidates.remove(0)),
+            _ => Err(ReplayErrorKind::Ambiguous),
+        }
+    }
+
+    // resolves a parsed move and plays it
+    pub fn play(&mut self, mv: &ChessMove) -> Result<PlayedMove, ReplayErrorKind> {
+        let played = self.resolve_move(mv)?;
+        self.make_move(&played);
+        Ok(played)
+    }
+}
+
+fn matches_move(candidate: &PlayedMove, mv: &ChessMove) -> bool {
+    if let Some(side) = mv.castling {
+        let kingside = side == CastlingSide::Kingside;
+        return candidate.castling && (candidate.to.file.index() == 6) == kingside;
+    }
+    // coordinates name the squares, whatever piece stands there
+    if mv.notation == MoveNotation::Uci {
+        return mv.disambig == Some(Disambiguation::Square(candidate.from))
+            && mv.target() == Some(candidate.to)
+            && candidate.promotion == mv.promotion;
+    }
+    if candidate.castling || candidate.piece != mv.piece {
+        return false;
+    }
+    if mv.target() != Some(candidate.to) {
+        return false;
+    }
+    if candidate.promotion != mv.promotion {
+        return false;
+    }
+    // "x" must land on a piece, and a move onto a piece must say "x"
+    if mv.capture != candidate.captured.is_some() {
+        return false;
+    }
+
+    mv.disambig
+        .map_or(true, |disambig| disambig.matches(candidate.from))
+}
+
+impl ChessGame {
+    // position the game starts from - the FEN tag when the game is set up, otherwise the standard one
+    pub fn starting_position(&self) -> Result<Board, FenError> {
+        let set_up = self.metadata.get("SetUp").map(|value| value.trim());
+        match self.metadata.get("FEN") {
+            Some(fen) if set_up != Some("0") => Board::from_fen(fen),
+            _ => Ok(Board::default()),
+        }
+    }
+}
+
+// replays the main line of a game from its starting position, resolving every move
+pub fn replay_game(game: &ChessGame) -> Result<Vec<PlayedMove>, ReplayError> {
+    replay(game).map(|(played, _)| played)
+}
+
+// positions of the main line - the starting position, then the position after each ply.
+// the FEN after ply n is `replay_positions(&game)?[n].to_fen()`
+pub fn replay_positions(game: &ChessGame) -> Result<Vec<Board>, ReplayError> {
+    replay(game).map(|(_, positions)| positions)
+}
+
+// starting position of a replay, with a bad FEN tag reported like an unplayable move
+pub(crate) fn replay_start(game: &ChessGame) -> Result<Board, ReplayError> {
+    game.starting_position().map_err(|error| ReplayError {
+        turn_number: 0,
+        color: Color::White,
+        move_text: game.metadata.get("FEN").unwrap_or_default().to_string(),
+        kind: ReplayErrorKind::InvalidFen(error),
+        line: 0,
+        column: 0,
+    })
+}
+
+fn replay(game: &ChessGame) -> Result<(Vec<PlayedMove>, Vec<Board>), ReplayError> {
+    let mut board = replay_start(game)?;
+    let mut played = Vec::new();
+    let mut positions = vec![board.clone()];
+
+    for turn in &game.turns {
+        let moves = [
+            (Color::White, &turn.white_move),
+            (Color::Black, &turn.black_move),
+        ];
+        for (color, mv) in moves {
+            let Some(mv) = mv.as_ref().filter(|mv| mv.is_played()) else {
+                continue;
+            };
+
+            let result = if color != board.side_to_move {
+                Err(ReplayErrorKind::OutOfTurn)
+            } else {
+                board.play(mv)
+            };
+            match result {
+                Ok(played_move) => {
+                    played.push(played_move);
+                    positions.push(board.clone());
+                }
+                Err(kind) => return Err(ReplayError::at_move(turn.turn_number, color, mv, kind)),
+            }
+        }
+    }
+
+    Ok((played, positions))
+}
+
+// replays a line from `board`, and every variation from the position it branches off at.
+// each move is handed to `visit` with the position before it and the move found on the board
+pub(crate) fn replay_lines<F>(
+    board: &mut Board,
+    turns: &mut [ChessTurn],
+    visit: &mut F,
+) -> Result<(), ReplayError>
+where
+    F: FnMut(&Board, &PlayedMove, &mut ChessMove),
+{
+    for turn in turns {
+        let moves = [
+            (Color::White, &mut turn.white_move),
+            (Color::Black, &mut turn.black_move),
+        ];
+        for (color, mv) in moves {
+            let Some(mv) = mv.as_mut().filter(|mv| mv.is_played()) else {
+                continue;
+            };
+
+            let played = if color != board.side_to_move {
+                Err(ReplayErrorKind::OutOfTurn)
+            } else {
+                board.resolve_move(mv)
+            }
+            .map_err(|kind| ReplayError::at_move(turn.turn_number, color, mv, kind))?;
+
+            // variations replace the move, so they start from the position before it
+            for variation in &mut mv.variations {
+                replay_lines(&mut board.clone(), &mut variation.turns, visit)?;
+            }
+
+            visit(board, &played, mv);
+            board.make_move(&played);
+        }
+    }
+    Ok(())
+}
+
+impl ChessGame {
+    // marks the en passant captures of every line on their moves, returning how many there are.
+    // fails, leaving the lines after the bad move unmarked, when a move can't be replayed
+    pub fn mark_en_passant(&mut self) -> Result<usize, ReplayError> {
+        let mut board = replay_start(self)?;
+        let mut count = 0;
+        replay_lines(&mut board, &mut self.turns, &mut |_, played, mv| {
+            mv.en_passant = played.en_passant;
+            count += usize::from(played.en_passant);
+        })?;
+        Ok(count)
+    }
+}
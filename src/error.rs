@@ -0,0 +1,292 @@
+// errors returned by the parsing functions.
+// lines and columns are 1-based and refer to the original input, columns count characters
+
+use crate::board::{ReplayError, ReplayErrorKind};
+use crate::parser::Rule;
+use crate::types::Color;
+use crate::{ChessMove, ChessTurn};
+use pest::error::{Error, ErrorVariant, InputLocation, LineColLocation};
+use std::fmt;
+use std::io;
+
+#[derive(Debug)]
+pub enum PgnError {
+    // text that doesn't fit the PGN grammar
+    Syntax {
+        line: usize,
+        column: usize,
+        token: String,
+        expected: String,
+    },
+    // the input ends in the middle of a game
+    UnexpectedEnd {
+        line: usize,
+        column: usize,
+        expected: String,
+    },
+    // malformed or unusable tag pair
+    BadTag {
+        line: usize,
+        column: usize,
+        token: String,
+        reason: String,
+    },
+    // move that can't be played in the position it's recorded in
+    IllegalMove {
+        line: usize,
+        column: usize,
+        token: String,
+        turn_number: usize,
+        color: Color,
+        kind: ReplayErrorKind,
+    },
+    Io(io::Error),
+}
+
+impl PgnError {
+    // line and column the error was found at, if it comes from the input text
+    pub fn position(&self) -> Option<(usize, usize)> {
+        match self {
+            PgnError::Syntax { line, column, .. }
+            | PgnError::UnexpectedEnd { line, column, .. }
+            | PgnError::BadTag { line, column, .. }
+            | PgnError::IllegalMove { line, column, .. } => Some((*line, *column)),
+            PgnError::Io(_) => None,
+        }
+    }
+
+    // the offending piece of input
+    pub fn token(&self) -> Option<&str> {
+        match self {
+            PgnError::Syntax { token, .. }
+            | PgnError::BadTag { token, .. }
+            | PgnError::IllegalMove { token, .. } => Some(token),
+            PgnError::UnexpectedEnd { .. } | PgnError::Io(_) => None,
+        }
+    }
+
+    // moves the error's position by where the parsed text starts in the original input
+    pub(crate) fn shifted(mut self, offset: SourceOffset) -> PgnError {
+        match &mut self {
+            PgnError::Syntax { line, column, .. }
+            | PgnError::UnexpectedEnd { line, column, .. }
+            | PgnError::BadTag { line, column, .. }
+            | PgnError::IllegalMove { line, column, .. } => offset.apply(line, column),
+            PgnError::Io(_) => {}
+        }
+        self
+    }
+
+    // converts a grammar error on `text`, naming the token it stopped at
+    pub(crate) fn from_pest(error: Error<Rule>, text: &str) -> PgnError {
+        let (line, column) = match error.line_col {
+            LineColLocation::Pos(position) => position,
+            LineColLocation::Span(start, _) => start,
+        };
+        let offset = match error.location {
+            InputLocation::Pos(offset) => offset,
+            InputLocation::Span((start, _)) => start,
+        };
+        let expected = match &error.variant {
+            ErrorVariant::ParsingError { positives, .. } => describe_rules(positives),
+            ErrorVariant::CustomError { message } => message.clone(),
+        };
+
+        // inside a tag pair the whole pair is reported
+        let line_start = text[..offset].rfind('\n').map_or(0, |index| index + 1);
+        let line_before = &text[line_start..offset];
+        let tag_start = match line_before.rfind('[') {
+            Some(start) if !line_before[start..].contains(']') => Some(line_start + start),
+            _ => text[offset..].starts_with('[').then_some(offset),
+        };
+        if let Some(tag_start) = tag_start {
+            let line_end = text[tag_start..]
+                .find('\n')
+                .map_or(text.len(), |end| tag_start + end);
+            let tag_end = text[tag_start..line_end]
+                .find(']')
+                .map_or(line_end, |end| tag_start + end + 1);
+            return PgnError::BadTag {
+                line,
+                column: text[line_start..tag_start].chars().count() + 1,
+                token: text[tag_start..tag_end].trim_end().to_string(),
+                reason: format!("expected {}", expected),
+            };
+        }
+
+        let token: String = text[offset..]
+            .chars()
+            .take_while(|ch| !ch.is_whitespace())
+            .collect();
+        if text[offset..].trim().is_empty() {
+            PgnError::UnexpectedEnd {
+                line,
+                column,
+                expected,
+            }
+        } else {
+            PgnError::Syntax {
+                line,
+                column,
+                token,
+                expected,
+            }
+        }
+    }
+}
+
+// "expected chess move, comment or result"
+fn describe_rules(rules: &[Rule]) -> String {
+    let names: Vec<String> = rules
+        .iter()
+        .map(|rule| match rule {
+            Rule::EOI => "end of input".to_string(),
+            _ => format!("{:?}", rule).replace('_', " "),
+        })
+        .collect();
+    match names.split_last() {
+        None => "valid PGN".to_string(),
+        Some((last, [])) => last.clone(),
+        Some((last, rest)) => format!("{} or {}", rest.join(", "), last),
+    }
+}
+
+impl fmt::Display for PgnError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PgnError::Syntax {
+                line,
+                column,
+                token,
+                expected,
+            } => write!(
+                f,
+                "syntax error at line {}, column {}: unexpected '{}', expected {}",
+                line, column, token, expected
+            ),
+            PgnError::UnexpectedEnd {
+                line,
+                column,
+                expected,
+            } => write!(
+                f,
+                "unexpected end of input at line {}, column {}: expected {}",
+                line, column, expected
+            ),
+            PgnError::BadTag {
+                line,
+                column,
+                token,
+                reason,
+            } => write!(
+                f,
+                "bad tag '{}' at line {}, column {}: {}",
+                token, line, column, reason
+            ),
+            PgnError::IllegalMove {
+                line,
+                column,
+                token,
+                turn_number,
+                color,
+                kind,
+            } => {
+                let problem = match kind {
+                    ReplayErrorKind::Ambiguous => "ambiguous move",
+                    ReplayErrorKind::OutOfTurn => "move out of turn",
+                    _ => "illegal move",
+                };
+                write!(
+                    f,
+                    "{} '{}' in turn {} ({}) at line {}, column {}",
+                    problem, token, turn_number, color, line, column
+                )
+            }
+            PgnError::Io(error) => write!(f, "read error: {}", error),
+        }
+    }
+}
+
+impl std::error::Error for PgnError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            PgnError::Io(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for PgnError {
+    fn from(error: io::Error) -> Self {
+        PgnError::Io(error)
+    }
+}
+
+impl From<ReplayError> for PgnError {
+    fn from(error: ReplayError) -> Self {
+        match error.kind {
+            ReplayErrorKind::InvalidFen(fen_error) => PgnError::BadTag {
+                line: error.line,
+                column: error.column,
+                token: format!("[FEN \"{}\"]", error.move_text),
+                reason: fen_error.to_string(),
+            },
+            kind => PgnError::IllegalMove {
+                line: error.line,
+                column: error.column,
+                token: error.move_text,
+                turn_number: error.turn_number,
+                color: error.color,
+                kind,
+            },
+        }
+    }
+}
+
+// where a piece of parsed text starts in the original input
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct SourceOffset {
+    pub(crate) lines: usize,   // lines before the piece
+    pub(crate) columns: usize, // characters before the piece on its first line
+}
+
+impl SourceOffset {
+    // offset of `piece`, a slice of `text`
+    pub(crate) fn of_slice(text: &str, piece: &str) -> SourceOffset {
+        let start = piece.as_ptr() as usize - text.as_ptr() as usize;
+        let before = &text[..start];
+        let line_start = before.rfind('\n').map_or(0, |index| index + 1);
+        SourceOffset {
+            lines: before.matches('\n').count(),
+            columns: before[line_start..].chars().count(),
+        }
+    }
+
+    pub(crate) fn apply(self, line: &mut usize, column: &mut usize) {
+        if *line == 0 {
+            return; // unknown position
+        }
+        if *line == 1 {
+            *column += self.columns;
+        }
+        *line += self.lines;
+    }
+
+    pub(crate) fn apply_to_turns(self, turns: &mut [ChessTurn]) {
+        for turn in turns {
+            for mv in [&mut turn.white_move, &mut turn.black_move]
+                .into_iter()
+                .flatten()
+            {
+                self.apply_to_move(mv);
+            }
+        }
+    }
+
+    fn apply_to_move(self, mv: &mut ChessMove) {
+        self.apply(&mut mv.line, &mut mv.column);
+        for variation in &mut mv.variations {
+            self.apply_to_turns(&mut variation.turns);
+        }
+    }
+}
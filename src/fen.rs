@@ -0,0 +1,233 @@
+// Forsyth-Edwards Notation, describing a position in a single line:
+// piece placement, side to move, castling rights, en passant square, halfmove clock and fullmove number
+
+use crate::board::{Board, CastlingRights};
+use crate::types::{Color, Piece, Square};
+use std::fmt;
+
+pub const STARTING_FEN: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FenError {
+    FieldCount(usize), // FEN needs 4 to 6 space separated fields
+    Placement(String),
+    SideToMove(String),
+    Castling(String),
+    EnPassant(String),
+    MoveNumber(String),
+}
+
+impl fmt::Display for FenError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FenError::FieldCount(count) => {
+                write!(f, "expected 4 to 6 FEN fields, found {}", count)
+            }
+            FenError::Placement(field) => write!(f, "invalid piece placement '{}'", field),
+            FenError::SideToMove(field) => write!(f, "invalid side to move '{}'", field),
+            FenError::Castling(field) => write!(f, "invalid castling rights '{}'", field),
+            FenError::EnPassant(field) => write!(f, "invalid en passant square '{}'", field),
+            FenError::MoveNumber(field) => write!(f, "invalid move number '{}'", field),
+        }
+    }
+}
+
+impl std::error::Error for FenError {}
+
+// FEN letter of a piece, uppercase for white
+fn piece_letter(color: Color, piece: Piece) -> char {
+    let letter = piece.letter();
+    match color {
+        Color::White => letter,
+        Color::Black => letter.to_ascii_lowercase(),
+    }
+}
+
+fn parse_placement(field: &str, board: &mut Board) -> Result<(), FenError> {
+    let error = || FenError::Placement(field.to_string());
+
+    let ranks: Vec<&str> = field.split('/').collect();
+    if ranks.len() != 8 {
+        return Err(error());
+    }
+
+    // FEN lists the ranks from the 8th down to the 1st
+    for (index, rank_text) in ranks.iter().enumerate() {
+        let rank = 7 - index as u8;
+        let mut file = 0;
+        for ch in rank_text.chars() {
+            if let Some(empty) = ch.to_digit(10).filter(|n| (1..=8).contains(n)) {
+                file += empty as u8;
+                if file > 8 {
+                    return Err(error());
+                }
+                continue;
+            }
+
+            let color = if ch.is_ascii_uppercase() {
+                Color::White
+            } else {
+                Color::Black
+            };
+            let piece = Piece::from_letter(ch.to_ascii_uppercase()).ok_or_else(error)?;
+            let square = Square::from_indices(file, rank).ok_or_else(error)?;
+            board.set_piece(square, Some((color, piece)));
+            file += 1;
+        }
+        if file != 8 {
+            return Err(error());
+        }
+    }
+
+    // both sides need exactly one king for the position to be playable
+    for color in [Color::White, Color::Black] {
+        let kings = Square::all()
+            .filter(|&square| board.piece_at(square) == Some((color, Piece::King)))
+            .count();
+        if kings != 1 {
+            return Err(error());
+        }
+    }
+    Ok(())
+}
+
+fn parse_castling(field: &str) -> Result<CastlingRights, FenError> {
+    let mut rights = CastlingRights {
+        white_kingside: false,
+        white_queenside: false,
+        black_kingside: false,
+        black_queenside: false,
+    };
+    if field == "-" {
+        return Ok(rights);
+    }
+
+    for ch in field.chars() {
+        let right = match ch {
+            'K' => &mut rights.white_kingside,
+            'Q' => &mut rights.white_queenside,
+            'k' => &mut rights.black_kingside,
+            'q' => &mut rights.black_queenside,
+            _ => return Err(FenError::Castling(field.to_string())),
+        };
+        if *right {
+            return Err(FenError::Castling(field.to_string()));
+        }
+        *right = true;
+    }
+    Ok(rights)
+}
+
+fn parse_en_passant(field: &str, side_to_move: Color) -> Result<Option<Square>, FenError> {
+    if field == "-" {
+        return Ok(None);
+    }
+
+    let error = || FenError::EnPassant(field.to_string());
+    let square: Square = field.parse().map_err(|_| error())?;
+
+    // the square is behind a pawn that just moved two squares
+    let expected_rank = match side_to_move {
+        Color::White => '6',
+        Color::Black => '3',
+    };
+    if square.rank.to_char() != expected_rank {
+        return Err(error());
+    }
+    Ok(Some(square))
+}
+
+impl Board {
+    // position described by a FEN string, the move counters may be left out
+    pub fn from_fen(fen: &str) -> Result<Board, FenError> {
+        let fields: Vec<&str> = fen.split_whitespace().collect();
+        if !(4..=6).contains(&fields.len()) {
+            return Err(FenError::FieldCount(fields.len()));
+        }
+
+        let mut board = Board::empty();
+        parse_placement(fields[0], &mut board)?;
+
+        board.side_to_move = match fields[1] {
+            "w" => Color::White,
+            "b" => Color::Black,
+            other => return Err(FenError::SideToMove(other.to_string())),
+        };
+        board.castling = parse_castling(fields[2])?;
+        board.en_passant = parse_en_passant(fields[3], board.side_to_move)?;
+
+        if let Some(clock) = fields.get(4) {
+            board.halfmove_clock = clock
+                .parse()
+                .map_err(|_| FenError::MoveNumber(clock.to_string()))?;
+        }
+        if let Some(number) = fields.get(5) {
+            board.fullmove_number = number
+                .parse()
+                .ok()
+                .filter(|&number| number > 0)
+                .ok_or_else(|| FenError::MoveNumber(number.to_string()))?;
+        }
+
+        Ok(board)
+    }
+
+    pub fn to_fen(&self) -> String {
+        let mut placement = String::new();
+        for rank in (0..8).rev() {
+            let mut empty = 0;
+            for file in 0..8 {
+                match Square::from_indices(file, rank).and_then(|square| self.piece_at(square)) {
+                    Some((color, piece)) => {
+                        if empty > 0 {
+                            placement.push_str(&empty.to_string());
+                            empty = 0;
+                        }
+                        placement.push(piece_letter(color, piece));
+                    }
+                    None => empty += 1,
+                }
+            }
+            if empty > 0 {
+                placement.push_str(&empty.to_string());
+            }
+            if rank > 0 {
+                placement.push('/');
+            }
+        }
+
+        let side_to_move = match self.side_to_move {
+            Color::White => "w",
+            Color::Black => "b",
+        };
+
+        let rights = [
+            (self.castling.white_kingside, 'K'),
+            (self.castling.white_queenside, 'Q'),
+            (self.castling.black_kingside, 'k'),
+            (self.castling.black_queenside, 'q'),
+        ];
+        let mut castling: String = rights
+            .iter()
+            .filter(|(allowed, _)| *allowed)
+            .map(|(_, letter)| letter)
+            .collect();
+        if castling.is_empty() {
+            castling.push('-');
+        }
+
+        let en_passant = self
+            .en_passant
+            .map_or("-".to_string(), |square| square.to_string());
+
+        format!(
+            "{} {} {} {} {} {}",
+            placement,
+            side_to_move,
+            castling,
+            en_passant,
+            self.halfmove_clock,
+            self.fullmove_number
+        )
+    }
+}
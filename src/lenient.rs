@@ -0,0 +1,337 @@
+// error-recovering parsing: unparseable tokens are blanked out and the game is parsed again,
+// recording a diagnostic for every problem skipped on the way. a skipped move is first
+// replaced by a null move, so the moves after it keep their color, and its slot is emptied
+// once the game parses
+
+use crate::error::SourceOffset;
+use crate::{
+    parse_pgn_text, split_pgn_games, ChessGame, ChessMove, ChessTurn, ParseOptions, PgnError,
+};
+use std::fmt;
+
+// problems recovered from in a single game before giving up on it
+pub const MAX_RECOVERIES: usize = 100;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiagnosticKind {
+    Syntax,
+    UnexpectedEnd,
+    BadTag,
+    IllegalMove,
+    SkippedGame, // nothing could be recovered from the game
+}
+
+// a problem found (and skipped) by a lenient parse
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub kind: DiagnosticKind,
+    pub line: usize, // 1-based position in the input, 0 when unknown
+    pub column: usize,
+    pub snippet: String, // the offending piece of input
+    pub message: String,
+}
+
+impl From<&PgnError> for Diagnostic {
+    fn from(error: &PgnError) -> Self {
+        let kind = match error {
+            PgnError::Syntax { .. } | PgnError::Io(_) => DiagnosticKind::Syntax,
+            PgnError::UnexpectedEnd { .. } => DiagnosticKind::UnexpectedEnd,
+            PgnError::BadTag { .. } => DiagnosticKind::BadTag,
+            PgnError::IllegalMove { .. } => DiagnosticKind::IllegalMove,
+        };
+        let (line, column) = error.position().unwrap_or((0, 0));
+        Diagnostic {
+            kind,
+            line,
+            column,
+            snippet: error.token().unwrap_or_default().to_string(),
+            message: error.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+// parses a game, skipping whatever can't be parsed.
+// a game nothing could be recovered from comes back empty, with a SkippedGame diagnostic
+pub fn parse_pgn_lenient(pgn_text: &str) -> (ChessGame, Vec<Diagnostic>) {
+    parse_pgn_lenient_with_options(pgn_text, &ParseOptions::default())
+}
+
+pub fn parse_pgn_lenient_with_options(
+    pgn_text: &str,
+    options: &ParseOptions,
+) -> (ChessGame, Vec<Diagnostic>) {
+    let (game, diagnostics) = parse_lenient_at_offset(pgn_text, options, SourceOffset::default());
+    (game.unwrap_or_default(), diagnostics)
+}
+
+// parses every game of a database leniently, leaving out the games that had to be skipped
+pub fn parse_pgn_database_lenient(
+    pgn_text: &str,
+    options: &ParseOptions,
+) -> (Vec<ChessGame>, Vec<Diagnostic>) {
+    let mut games = Vec::new();
+    let mut diagnostics = Vec::new();
+
+    for game_text in split_pgn_games(pgn_text) {
+        let offset = SourceOffset::of_slice(pgn_text, game_text);
+        let (game, game_diagnostics) = parse_lenient_at_offset(game_text, options, offset);
+        games.extend(game);
+        diagnostics.extend(game_diagnostics);
+    }
+
+    (games, diagnostics)
+}
+
+// lenient parse of a game whose text starts at `offset` in the original input
+pub(crate) fn parse_lenient_at_offset(
+    pgn_text: &str,
+    options: &ParseOptions,
+    offset: SourceOffset,
+) -> (Option<ChessGame>, Vec<Diagnostic>) {
+    let mut diagnostics: Vec<Diagnostic> = Vec::new();
+    let mut text = pgn_text.to_string();
+    let mut placeholders: Vec<(usize, usize)> = Vec::new(); // null moves standing in for skipped ones
+    let mut grammar_options = ParseOptions {
+        validate_moves: false,
+        ..*options
+    };
+
+    for _ in 0..MAX_RECOVERIES {
+        let error = match parse_pgn_text(&text, &grammar_options, true) {
+            Ok(mut game) => {
+                let recovered = !game.metadata.is_empty() || !game.turns.is_empty();
+                if diagnostics.is_empty() || recovered {
+                    if options.validate_moves {
+                        let validating = ParseOptions {
+                            validate_moves: true,
+                            ..grammar_options
+                        };
+                        // a skipped move is replayed as a pass, which may not be possible
+                        match parse_pgn_text(&text, &validating, true) {
+                            Err(error)
+                                if !error
+                                    .position()
+                                    .is_some_and(|position| placeholders.contains(&position)) =>
+                            {
+                                diagnostics.push(Diagnostic::from(&error.shifted(offset)));
+                            }
+                            _ => {}
+                        }
+                    }
+                    empty_placeholders(&mut game.turns, &placeholders);
+                    offset.apply_to_turns(&mut game.turns);
+                    diagnostics.sort_by_key(|diagnostic| (diagnostic.line, diagnostic.column));
+                    return (Some(game), diagnostics);
+                }
+                break;
+            }
+            Err(error) => error,
+        };
+
+        let recovered = match &error {
+            // a missing result is fine once it's been reported, anything else is dropped from the end
+            PgnError::UnexpectedEnd { .. } if grammar_options.require_result => {
+                grammar_options.require_result = false;
+                true
+            }
+            PgnError::UnexpectedEnd { .. } => blank_last_token(&mut text),
+            _ => match skip_error_token(&text, &error, &grammar_options) {
+                Some((skipped, placeholder)) => {
+                    text = skipped;
+                    placeholders.extend(placeholder);
+                    true
+                }
+                None => blank_last_token(&mut text),
+            },
+        };
+
+        let diagnostic = Diagnostic::from(&error.shifted(offset));
+        // dropping the last token may hit the end of input again, which is already reported
+        let repeated = diagnostic.kind == DiagnosticKind::UnexpectedEnd
+            && diagnostics
+                .last()
+                .is_some_and(|last| last.kind == DiagnosticKind::UnexpectedEnd);
+        if !repeated {
+            diagnostics.push(diagnostic);
+        }
+        if !recovered {
+            break;
+        }
+    }
+
+    diagnostics.sort_by_key(|diagnostic| (diagnostic.line, diagnostic.column));
+    // the game's position is where its text starts, past any blank lines
+    let game_start = SourceOffset::of_slice(pgn_text, pgn_text.trim_start());
+    let (mut line, mut column) = (game_start.lines + 1, game_start.columns + 1);
+    offset.apply(&mut line, &mut column);
+    diagnostics.push(Diagnostic {
+        kind: DiagnosticKind::SkippedGame,
+        line,
+        column,
+        snippet: pgn_text
+            .trim()
+            .lines()
+            .next()
+            .unwrap_or_default()
+            .to_string(),
+        message: format!("game at line {}, column {} skipped", line, column),
+    });
+    (None, diagnostics)
+}
+
+// text with a token skipped, and the position of the null move standing in for it
+type Skip = (String, Option<(usize, usize)>);
+
+// text with the token the error points at blanked out, or - when the grammar only noticed
+// the problem a token later ("1. c Nf3") - the token before it, whichever lets the parser get further.
+// a move is replaced by a null move where that parses as far, and its position is returned.
+// every other position stays intact
+fn skip_error_token(text: &str, error: &PgnError, options: &ParseOptions) -> Option<Skip> {
+    let (line, column) = error.position()?;
+    let token = error.token()?;
+    let start = byte_offset(text, line, column)?;
+    if token.trim().is_empty() || !text[start..].starts_with(token) {
+        return None;
+    }
+    let end = start + token.len();
+    match error {
+        PgnError::IllegalMove { .. } => {
+            return Some(match null_placeholder(text, start, end) {
+                Some(placeholder) => (placeholder, Some((line, column))),
+                None => (blanked(text, start, end), None),
+            });
+        }
+        PgnError::Syntax { .. } => {}
+        _ => return Some((blanked(text, start, end), None)),
+    }
+
+    // the grammar may stop in the middle of a move ("e9"), which is dropped as a whole
+    let move_start = token_start(text, start);
+    let move_column = column - text[move_start..start].chars().count();
+    let mut candidates = Vec::new();
+    if let Some(placeholder) = null_placeholder(text, move_start, end) {
+        candidates.push((placeholder, Some((line, move_column))));
+    }
+    candidates.push((blanked(text, move_start, end), None));
+
+    let before = text[..move_start].trim_end();
+    let previous_start = token_start(before, before.len());
+    if previous_start < before.len() {
+        candidates.push((blanked(text, previous_start, before.len()), None));
+    }
+
+    // the first of the candidates getting furthest
+    let mut best: Option<(usize, Skip)> = None;
+    for candidate in candidates {
+        let progress = parse_progress(&candidate.0, options);
+        if best.as_ref().map_or(true, |(best, _)| progress > *best) {
+            best = Some((progress, candidate));
+        }
+    }
+    best.map(|(_, candidate)| candidate)
+}
+
+// the text with a range on one line replaced by a null move, when it is long enough to hold one
+fn null_placeholder(text: &str, start: usize, end: usize) -> Option<String> {
+    let length = text[start..end].chars().count();
+    if length < 2 || text[start..end].contains('\n') {
+        return None;
+    }
+    let mut placeholder = text.to_string();
+    placeholder.replace_range(start..end, &format!("--{}", " ".repeat(length - 2)));
+    Some(placeholder)
+}
+
+fn blanked(text: &str, start: usize, end: usize) -> String {
+    let mut blanked = text.to_string();
+    blank_range(&mut blanked, start, end);
+    blanked
+}
+
+// empties the slots of the null moves standing in for skipped moves
+fn empty_placeholders(turns: &mut [ChessTurn], placeholders: &[(usize, usize)]) {
+    let is_placeholder =
+        |mv: &ChessMove| mv.null_move && placeholders.contains(&(mv.line, mv.column));
+    for turn in turns {
+        for slot in [&mut turn.white_move, &mut turn.black_move] {
+            if slot.as_ref().is_some_and(is_placeholder) {
+                *slot = None;
+            }
+            if let Some(mv) = slot {
+                for variation in &mut mv.variations {
+                    empty_placeholders(&mut variation.turns, placeholders);
+                }
+            }
+        }
+    }
+}
+
+// start of the movetext token ending at `end`, without crossing brackets, braces, quotes or move numbers
+fn token_start(text: &str, end: usize) -> usize {
+    let mut start = end;
+    for (index, ch) in text[..end].char_indices().rev() {
+        if ch.is_whitespace() || "(){}[]\";.".contains(ch) {
+            break;
+        }
+        start = index;
+    }
+    start
+}
+
+// how far into the text the grammar gets
+fn parse_progress(text: &str, options: &ParseOptions) -> usize {
+    match parse_pgn_text(text, options, true) {
+        Ok(_) => usize::MAX,
+        Err(PgnError::UnexpectedEnd { .. }) => text.len(),
+        Err(error) => error
+            .position()
+            .and_then(|(line, column)| byte_offset(text, line, column))
+            .unwrap_or(0),
+    }
+}
+
+// blanks the last whitespace-separated token of the text
+fn blank_last_token(text: &mut String) -> bool {
+    let trimmed = text.trim_end();
+    if trimmed.is_empty() {
+        return false;
+    }
+    let end = trimmed.len();
+    let start = trimmed
+        .char_indices()
+        .rev()
+        .find(|(_, ch)| ch.is_whitespace())
+        .map_or(0, |(index, ch)| index + ch.len_utf8());
+    blank_range(text, start, end);
+    true
+}
+
+fn blank_range(text: &mut String, start: usize, end: usize) {
+    let blanked: String = text[start..end]
+        .chars()
+        .map(|ch| if ch == '\n' { '\n' } else { ' ' })
+        .collect();
+    text.replace_range(start..end, &blanked);
+}
+
+// byte offset of a 1-based line and character column
+fn byte_offset(text: &str, line: usize, column: usize) -> Option<usize> {
+    let line_start = if line <= 1 {
+        0
+    } else {
+        text.match_indices('\n').nth(line - 2)?.0 + 1
+    };
+    let line_text = &text[line_start..];
+    let in_line = line_text
+        .char_indices()
+        .map(|(index, _)| index)
+        .chain(std::iter::once(line_text.len()))
+        .nth(column.checked_sub(1)?)?;
+    Some(line_start + in_line)
+}
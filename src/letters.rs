@@ -0,0 +1,119 @@
+// piece letters of other languages and figurines, translated to and from the English
+// letters the grammar and the move model use
+
+use crate::scanner::{PgnScanner, TextKind};
+use crate::types::Piece;
+use std::str::FromStr;
+
+// the symbols a PGN text uses for the pieces
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PieceLetters {
+    letters: [char; 6], // pawn, knight, bishop, rook, queen, king
+}
+
+impl PieceLetters {
+    pub const ENGLISH: PieceLetters = PieceLetters::new('K', 'Q', 'R', 'B', 'N', 'P');
+    // König, Dame, Turm, Läufer, Springer, Bauer
+    pub const GERMAN: PieceLetters = PieceLetters::new('K', 'D', 'T', 'L', 'S', 'B');
+    // roi, dame, tour, fou, cavalier, pion
+    pub const FRENCH: PieceLetters = PieceLetters::new('R', 'D', 'T', 'F', 'C', 'P');
+    // the white figurines - black ones are read as well
+    pub const FIGURINE: PieceLetters = PieceLetters::new('♔', '♕', '♖', '♗', '♘', '♙');
+
+    pub const fn new(
+        king: char,
+        queen: char,
+        rook: char,
+        bishop: char,
+        knight: char,
+        pawn: char,
+    ) -> PieceLetters {
+        PieceLetters {
+            letters: [pawn, knight, bishop, rook, queen, king],
+        }
+    }
+
+    pub fn letter(&self, piece: Piece) -> char {
+        self.letters[piece as usize]
+    }
+
+    pub fn piece(&self, letter: char) -> Option<Piece> {
+        // black figurines follow the white ones, from '♚' to '♟'
+        let letter = match letter {
+            '♚'..='♟' => char::from_u32(letter as u32 - 6).unwrap_or(letter),
+            _ => letter,
+        };
+        let index = self.letters.iter().position(|&known| known == letter)?;
+        [
+            Piece::Pawn,
+            Piece::Knight,
+            Piece::Bishop,
+            Piece::Rook,
+            Piece::Queen,
+            Piece::King,
+        ]
+        .get(index)
+        .copied()
+    }
+
+    // a move spelled with English letters ("Nf3", "e8=Q") in these letters ("Sf3", "e8=D")
+    pub fn translate_move(&self, mv: &str) -> String {
+        let mut translated = String::with_capacity(mv.len());
+        let mut previous = None;
+        for ch in mv.chars() {
+            match Piece::from_letter(ch) {
+                // the piece leads the move or follows the promotion sign
+                Some(piece) if previous.is_none() || previous == Some('=') => {
+                    translated.push(self.letter(piece))
+                }
+                _ => translated.push(ch),
+            }
+            previous = Some(ch);
+        }
+        translated
+    }
+
+    // PGN text with the piece letters of its moves turned into English ones. tags and comments
+    // are left alone, and every letter stays one character, so line and column numbers hold
+    pub(crate) fn english_text(&self, text: &str) -> String {
+        let mut english = String::with_capacity(text.len());
+        let mut scanner = PgnScanner::default();
+        let mut previous: Option<char> = None;
+
+        for ch in text.chars() {
+            let mut translated = ch;
+            if scanner.kind(ch) == TextKind::Movetext {
+                // only a letter opening a move or naming a promotion is a piece
+                let starts_piece = previous.map_or(true, |previous| {
+                    previous.is_whitespace() || ".()=}".contains(previous)
+                });
+                if let Some(piece) = self.piece(ch).filter(|_| starts_piece) {
+                    translated = piece.letter();
+                }
+            }
+            english.push(translated);
+            previous = Some(ch);
+        }
+        english
+    }
+}
+
+impl Default for PieceLetters {
+    fn default() -> Self {
+        PieceLetters::ENGLISH
+    }
+}
+
+impl FromStr for PieceLetters {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name.to_ascii_lowercase().as_str() {
+            "english" | "en" => Ok(PieceLetters::ENGLISH),
+            "german" | "de" => Ok(PieceLetters::GERMAN),
+            "french" | "fr" => Ok(PieceLetters::FRENCH),
+            "figurine" => Ok(PieceLetters::FIGURINE),
+            _ => Err(format!("unknown piece letters '{}'", name)),
+        }
+    }
+}
@@ -6,11 +6,90 @@ pub mod parser {
     pub struct ChessParser;
 }
 
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub struct ChessGame {
-    pub metadata: std::collections::HashMap<String, String>,
+    pub metadata: Tags, // tag pairs in source order
     pub turns: Vec<ChessTurn>,
-    pub result: String,
+    pub comments: Vec<String>, // comments with no move to go with, in a game without moves
+    pub result: GameResult,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum GameResult {
+    WhiteWins,
+    BlackWins,
+    Draw,
+    #[default]
+    Unknown, // "*" - ongoing, abandoned or not given
+}
+
+impl std::fmt::Display for GameResult {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let result = match self {
+            GameResult::WhiteWins => "1-0",
+            GameResult::BlackWins => "0-1",
+            GameResult::Draw => "1/2-1/2",
+            GameResult::Unknown => "*",
+        };
+        write!(f, "{}", result)
+    }
+}
+
+impl std::str::FromStr for GameResult {
+    type Err = String;
+
+    fn from_str(result: &str) -> Result<Self, Self::Err> {
+        match result {
+            "1-0" => Ok(GameResult::WhiteWins),
+            "0-1" => Ok(GameResult::BlackWins),
+            "1/2-1/2" => Ok(GameResult::Draw),
+            "*" => Ok(GameResult::Unknown),
+            _ => Err(format!("invalid game result '{}'", result)),
+        }
+    }
+}
+
+// how a move is written
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MoveNotation {
+    #[default]
+    San, // "Nf3", "exd5", "O-O"
+    Long, // long algebraic, "Ng1-f3", "e4xd5"
+    Uci,  // coordinates, "g1f3", "e7e8q"
+}
+
+impl std::fmt::Display for MoveNotation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let notation = match self {
+            MoveNotation::San => "SAN",
+            MoveNotation::Long => "long algebraic",
+            MoveNotation::Uci => "UCI",
+        };
+        write!(f, "{}", notation)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct ParseOptions {
+    // when false, movetext without a result token is accepted (with an unknown result)
+    pub require_result: bool,
+    // when true, the main line is replayed and games with illegal moves are rejected
+    pub validate_moves: bool,
+    // the only notation moves may be written in, any of them when none
+    pub notation: Option<MoveNotation>,
+    // letters or figurines naming the pieces in moves
+    pub piece_letters: PieceLetters,
+}
+
+impl Default for ParseOptions {
+    fn default() -> Self {
+        ParseOptions {
+            require_result: true,
+            validate_moves: false,
+            notation: None,
+            piece_letters: PieceLetters::ENGLISH,
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -32,15 +111,29 @@ impl Default for ChessTurn {
 
 #[derive(Debug)]
 pub struct ChessMove {
-    pub full_str: String,
-    pub special: bool,
+    pub full_str: String, // as written, but with English piece letters
+    pub special: bool,    // castling
+    pub null_move: bool,  // "--" or "Z0", passing the turn
+    pub castling: Option<CastlingSide>,
     pub capture: bool,
-    pub piece: char,
-    pub promotion: char,
-    pub loc_col: char,
-    pub loc_row: i8,
-    pub annotation: String,
-    pub disambig: String,
+    pub en_passant: bool, // captures en passant, as found by replaying the game
+    pub en_passant_suffix: bool, // "e.p." written after the move, which isn't part of full_str
+    pub piece: Piece,     // the king for castling
+    pub promotion: Option<Piece>,
+    pub loc_col: Option<File>, // target square, none for castling
+    pub loc_row: Option<Rank>,
+    pub annotation: String, // full suffix as written, e.g. "+!"
+    pub check: bool,
+    pub mate: bool,
+    pub quality: String, // !!, !, !?, ?!, ?, ??
+    pub disambig: Option<Disambiguation>,
+    pub notation: MoveNotation,
+    pub comments: Vec<String>,
+    pub pre_comments: Vec<String>,
+    pub nags: Vec<u8>,
+    pub variations: Vec<ChessVariation>,
+    pub line: usize, // position of the move in the source (1-based), 0 when not parsed from text
+    pub column: usize,
 }
 
 impl Default for ChessMove {
@@ -48,275 +141,900 @@ impl Default for ChessMove {
         ChessMove {
             full_str: String::new(),
             special: false,
+            null_move: false,
+            castling: None,
             capture: false,
-            piece: 'p', // p/K/Q/B/R/N
-            loc_col: '-',
-            loc_row: 0,
-            promotion: ' ',
+            en_passant: false,
+            en_passant_suffix: false,
+            piece: Piece::Pawn,
+            loc_col: None,
+            loc_row: None,
+            promotion: None,
             annotation: String::new(),
-            disambig: String::new(),
+            check: false,
+            mate: false,
+            quality: String::new(),
+            disambig: None,
+            notation: MoveNotation::San,
+            comments: Vec::new(),
+            pre_comments: Vec::new(),
+            nags: Vec::new(),
+            variations: Vec::new(),
+            line: 0,
+            column: 0,
+        }
+    }
+}
+
+// side line replacing the move it is attached to, itself possibly holding further variations
+#[derive(Debug, Default)]
+pub struct ChessVariation {
+    pub turns: Vec<ChessTurn>,
+    pub comments: Vec<String>, // comments with no move to go with, in a variation without moves
+}
+
+impl ChessMove {
+    // square the move goes to, none for castling
+    pub fn target(&self) -> Option<Square> {
+        Some(Square::new(self.loc_col?, self.loc_row?))
+    }
+
+    // false for the empty black move of a turn the game ends in
+    pub fn is_played(&self) -> bool {
+        !self.full_str.is_empty()
+    }
+}
+
+impl ChessGame {
+    // moves of the main line, in the order they were played
+    pub fn mainline(&self) -> Vec<&ChessMove> {
+        turns_moves(&self.turns)
+    }
+
+    // makes variation `index` of the move at `ply` (0-based, over mainline moves) the new main line.
+    // the replaced moves become the first variation of the promoted line's first move
+    pub fn promote_variation(&mut self, ply: usize, index: usize) -> bool {
+        let promoted = promote_turns_variation(&mut self.turns, ply, index);
+
+        // keep the empty black move of a game ending mid-turn
+        if let Some(turn) = self.turns.last_mut() {
+            if turn.white_move.is_some() {
+                turn.black_move.get_or_insert_with(ChessMove::default);
+            }
+        }
+        promoted
+    }
+}
+
+impl ChessVariation {
+    pub fn moves(&self) -> Vec<&ChessMove> {
+        turns_moves(&self.turns)
+    }
+
+    pub fn promote_variation(&mut self, ply: usize, index: usize) -> bool {
+        promote_turns_variation(&mut self.turns, ply, index)
+    }
+}
+
+fn turns_moves(turns: &[ChessTurn]) -> Vec<&ChessMove> {
+    turns
+        .iter()
+        .flat_map(|turn| [&turn.white_move, &turn.black_move])
+        .flatten()
+        .filter(|mv| mv.is_played())
+        .collect()
+}
+
+// splits turns into their moves, along with the turn number and side of the first one
+fn turns_into_moves(turns: Vec<ChessTurn>) -> (usize, bool, Vec<ChessMove>) {
+    let turn_number = turns.first().map_or(1, |turn| turn.turn_number);
+    let white_first = turns.first().map_or(true, |turn| turn.white_move.is_some());
+    let moves = turns
+        .into_iter()
+        .flat_map(|turn| [turn.white_move, turn.black_move])
+        .flatten()
+        .filter(ChessMove::is_played)
+        .collect();
+    (turn_number, white_first, moves)
+}
+
+fn moves_into_turns(
+    turn_number: usize,
+    white_first: bool,
+    moves: Vec<ChessMove>,
+) -> Vec<ChessTurn> {
+    let mut turns: Vec<ChessTurn> = Vec::new();
+    let mut turn_number = turn_number;
+    let mut white_to_move = white_first;
+
+    for mv in moves {
+        if white_to_move {
+            turns.push(ChessTurn {
+                turn_number,
+                white_move: Some(mv),
+                black_move: None,
+            });
+        } else {
+            match turns.last_mut() {
+                Some(turn) if turn.turn_number == turn_number => turn.black_move = Some(mv),
+                _ => turns.push(ChessTurn {
+                    turn_number,
+                    white_move: None,
+                    black_move: Some(mv),
+                }),
+            }
+            turn_number += 1;
         }
+        white_to_move = !white_to_move;
     }
+
+    turns
 }
 
+fn promote_turns_variation(turns: &mut Vec<ChessTurn>, ply: usize, index: usize) -> bool {
+    let (turn_number, white_first, mut moves) = turns_into_moves(std::mem::take(turns));
+
+    let has_variation = moves.get(ply).is_some_and(|mv| index < mv.variations.len());
+    if !has_variation {
+        *turns = moves_into_turns(turn_number, white_first, moves);
+        return false;
+    }
+
+    let mut replaced = moves.split_off(ply);
+    let variation = replaced[0].variations.remove(index);
+    let alternatives = std::mem::take(&mut replaced[0].variations);
+    let (_, _, mut promoted) = turns_into_moves(variation.turns);
+
+    // the old line starts at the same ply as the promoted one
+    let white_replaced = (ply % 2 == 0) == white_first;
+    let replaced_number = turn_number + (ply + usize::from(!white_first)) / 2;
+    let demoted = ChessVariation {
+        turns: moves_into_turns(replaced_number, white_replaced, replaced),
+        ..ChessVariation::default()
+    };
+
+    if let Some(first) = promoted.first_mut() {
+        let own_variations = std::mem::take(&mut first.variations);
+        first.variations.push(demoted);
+        first.variations.extend(alternatives);
+        first.variations.extend(own_variations);
+    }
+    moves.extend(promoted);
+
+    *turns = moves_into_turns(turn_number, white_first, moves);
+    true
+}
+
+mod board;
+mod error;
+mod fen;
+mod lenient;
+mod letters;
+mod nag;
+mod notation;
+mod reader;
+mod san;
+mod scanner;
+mod tags;
+mod types;
+mod validate;
+mod writer;
+
+pub use crate::board::{
+    replay_game, replay_positions, Board, CastlingRights, PlayedMove, ReplayError, ReplayErrorKind,
+};
+pub use crate::error::PgnError;
+pub use crate::fen::{FenError, STARTING_FEN};
+pub use crate::lenient::{
+    parse_pgn_database_lenient, parse_pgn_lenient, parse_pgn_lenient_with_options, Diagnostic,
+    DiagnosticKind, MAX_RECOVERIES,
+};
+pub use crate::letters::PieceLetters;
+pub use crate::nag::{nag_from_suffix, nag_meaning, nag_to_suffix};
+pub use crate::reader::PgnReader;
+pub use crate::tags::{Tags, SEVEN_TAG_ROSTER};
+pub use crate::types::{CastlingSide, Color, Disambiguation, File, Piece, Rank, Square};
+pub use crate::validate::{CheckMark, CheckMarkIssue, ResultIssue};
+
+use crate::error::SourceOffset;
+use crate::notation::{check_notation, resolve_uci_moves};
 use crate::parser::ChessParser;
 use crate::parser::Rule;
-use pest::error::Error;
+use crate::reader::GameSplitter;
+use crate::scanner::{PgnScanner, TextKind};
+use crate::tags::unescape_tag_value;
 use pest::iterators::Pair;
 use pest::Parser;
-use std::collections::HashMap;
 
-pub fn parse_pgn_parse_turn_num(pair: Pair<Rule>) -> usize {
-    let number_str = pair.as_str().trim_end_matches('.');
-    let number: usize = number_str.parse().expect("Failed to parse number");
-    return number;
+// error for text the grammar accepted but that can't be turned into a value
+fn parse_pgn_pair_error(pair: &Pair<Rule>, expected: &str) -> PgnError {
+    let (line, column) = pair.line_col();
+    PgnError::Syntax {
+        line,
+        column,
+        token: pair.as_str().to_string(),
+        expected: expected.to_string(),
+    }
 }
 
-pub fn parse_pgn_parse_getchar(rule: Pair<Rule>) -> char {
-    return rule.as_str().to_string().chars().nth(0).unwrap();
+pub fn parse_pgn_parse_turn_num(pair: Pair<Rule>) -> Result<usize, PgnError> {
+    // "12.", "12..." and "12. ..." all hold the number before the first dot
+    let number_str = pair.as_str().split('.').next().unwrap_or_default().trim();
+    number_str
+        .parse()
+        .map_err(|_| parse_pgn_pair_error(&pair, "a smaller turn number"))
 }
 
-pub fn parse_pgn_parse_row(rule: Pair<Rule>) -> i8 {
-    let number: i8 = rule
-        .as_str()
-        .to_string()
+pub fn parse_pgn_parse_getchar(rule: Pair<Rule>) -> Result<char, PgnError> {
+    rule.as_str()
+        .chars()
+        .next()
+        .ok_or_else(|| parse_pgn_pair_error(&rule, "a character"))
+}
+
+pub fn parse_pgn_parse_column(rule: Pair<Rule>) -> Result<File, PgnError> {
+    rule.as_str()
+        .parse()
+        .map_err(|_| parse_pgn_pair_error(&rule, "a column from a to h"))
+}
+
+pub fn parse_pgn_parse_row(rule: Pair<Rule>) -> Result<Rank, PgnError> {
+    rule.as_str()
+        .parse()
+        .map_err(|_| parse_pgn_pair_error(&rule, "a row number"))
+}
+
+pub fn parse_pgn_parse_piece_letter(rule: Pair<Rule>) -> Result<Piece, PgnError> {
+    rule.as_str()
         .parse()
-        .expect("Failed to parse number");
-    return number;
+        .map_err(|_| parse_pgn_pair_error(&rule, "a piece letter"))
 }
 
-pub fn parse_pgn_parse_location(loc_pair: Pair<Rule>, mv: &mut ChessMove) {
+pub fn parse_pgn_parse_promotion(
+    promo_pair: Pair<Rule>,
+    mv: &mut ChessMove,
+) -> Result<(), PgnError> {
+    for promo_ch in promo_pair.into_inner() {
+        if promo_ch.as_rule() == Rule::piece {
+            mv.promotion = Some(parse_pgn_parse_piece_letter(promo_ch)?);
+        }
+    }
+    Ok(())
+}
+
+pub fn parse_pgn_parse_location(loc_pair: Pair<Rule>, mv: &mut ChessMove) -> Result<(), PgnError> {
     for loc_ch in loc_pair.into_inner() {
         match loc_ch.as_rule() {
             Rule::column => {
-                mv.loc_col = parse_pgn_parse_getchar(loc_ch);
+                mv.loc_col = Some(parse_pgn_parse_column(loc_ch)?);
             }
             Rule::row => {
-                mv.loc_row = parse_pgn_parse_row(loc_ch);
+                mv.loc_row = Some(parse_pgn_parse_row(loc_ch)?);
             }
             _ => {}
         }
     }
+    Ok(())
 }
 
-pub fn parse_pgn_parse_pawn_cap(pair: Pair<Rule>, mv: &mut ChessMove) {
+pub fn parse_pgn_parse_pawn_cap(pair: Pair<Rule>, mv: &mut ChessMove) -> Result<(), PgnError> {
     for inner in pair.into_inner() {
         match inner.as_rule() {
             Rule::column => {
-                mv.disambig.push(parse_pgn_parse_getchar(inner));
+                mv.disambig = Some(Disambiguation::File(parse_pgn_parse_column(inner)?));
             }
             Rule::capture => {
                 mv.capture = true;
             }
             Rule::location => {
-                parse_pgn_parse_location(inner, mv);
+                parse_pgn_parse_location(inner, mv)?;
             }
             Rule::promotion => {
-                for promo_ch in inner.into_inner() {
-                    if promo_ch.as_rule() == Rule::piece {
-                        mv.promotion = parse_pgn_parse_getchar(promo_ch);
-                    }
-                }
+                parse_pgn_parse_promotion(inner, mv)?;
             }
             _ => {}
         }
     }
+    Ok(())
 }
 
-pub fn parse_pgn_parse_pawn_move(pair: Pair<Rule>, mv: &mut ChessMove) {
+pub fn parse_pgn_parse_pawn_move(pair: Pair<Rule>, mv: &mut ChessMove) -> Result<(), PgnError> {
     for inner in pair.into_inner() {
         match inner.as_rule() {
             Rule::location => {
-                parse_pgn_parse_location(inner, mv);
+                parse_pgn_parse_location(inner, mv)?;
             }
             Rule::promotion => {
-                for promo_ch in inner.into_inner() {
-                    if promo_ch.as_rule() == Rule::piece {
-                        mv.promotion = parse_pgn_parse_getchar(promo_ch);
-                    }
-                }
+                parse_pgn_parse_promotion(inner, mv)?;
             }
             _ => {}
         }
     }
+    Ok(())
 }
 
-pub fn parse_pgn_parse_piece(piece_pair: Pair<Rule>, mv: &mut ChessMove) {
+pub fn parse_pgn_parse_piece(piece_pair: Pair<Rule>, mv: &mut ChessMove) -> Result<(), PgnError> {
     for inner in piece_pair.into_inner() {
         if inner.as_rule() == Rule::piece {
-            mv.piece = parse_pgn_parse_getchar(inner);
+            mv.piece = parse_pgn_parse_piece_letter(inner)?;
         } else if inner.as_rule() == Rule::move_options {
-            parse_pgn_parse_move_options(inner, mv);
+            parse_pgn_parse_move_options(inner, mv)?;
         }
     }
+    Ok(())
 }
 
-pub fn parse_pgn_parse_move_options(move_pair: Pair<Rule>, mv: &mut ChessMove) {
-    let parsed = ChessParser::parse(Rule::options_util, move_pair.as_str());
-    if let Ok(parsed2) = parsed {
-        for out1 in parsed2 {
-            for out2 in out1.into_inner() {
-                match out2.as_rule() {
-                    Rule::disambig => {
-                        mv.disambig = out2.as_str().to_string();
-                    }
-                    Rule::capture => {
-                        mv.capture = true;
-                    }
-                    Rule::location => {
-                        parse_pgn_parse_location(out2, mv);
-                    }
-                    _ => {}
+pub fn parse_pgn_parse_disambig(disambig_pair: Pair<Rule>) -> Result<Disambiguation, PgnError> {
+    let error = parse_pgn_pair_error(&disambig_pair, "a file, rank or square");
+    match disambig_pair.into_inner().next() {
+        Some(inner) if inner.as_rule() == Rule::location => {
+            let mut location = ChessMove::default();
+            parse_pgn_parse_location(inner, &mut location)?;
+            location.target().map(Disambiguation::Square).ok_or(error)
+        }
+        Some(inner) if inner.as_rule() == Rule::column => {
+            Ok(Disambiguation::File(parse_pgn_parse_column(inner)?))
+        }
+        Some(inner) if inner.as_rule() == Rule::row => {
+            Ok(Disambiguation::Rank(parse_pgn_parse_row(inner)?))
+        }
+        _ => Err(error),
+    }
+}
+
+// square named by a location pair, e.g. the origin of a long algebraic move
+pub fn parse_pgn_parse_square(loc_pair: Pair<Rule>) -> Result<Square, PgnError> {
+    let error = parse_pgn_pair_error(&loc_pair, "a square");
+    let mut location = ChessMove::default();
+    parse_pgn_parse_location(loc_pair, &mut location)?;
+    location.target().ok_or(error)
+}
+
+// "Ng1-f3", "e4xd5": the piece (a pawn when left out), both squares and any promotion
+pub fn parse_pgn_parse_long_move(pair: Pair<Rule>, mv: &mut ChessMove) -> Result<(), PgnError> {
+    mv.notation = MoveNotation::Long;
+    for inner in pair.into_inner() {
+        match inner.as_rule() {
+            Rule::piece => {
+                mv.piece = parse_pgn_parse_piece_letter(inner)?;
+            }
+            // the origin comes first
+            Rule::location if mv.disambig.is_none() => {
+                mv.disambig = Some(Disambiguation::Square(parse_pgn_parse_square(inner)?));
+            }
+            Rule::location => {
+                parse_pgn_parse_location(inner, mv)?;
+            }
+            Rule::capture => {
+                mv.capture = true;
+            }
+            Rule::promotion => {
+                parse_pgn_parse_promotion(inner, mv)?;
+            }
+            _ => {}
+        }
+    }
+    // a piece capturing from a named square ("Qh4xe1") is SAN as well
+    if mv.capture && mv.piece != Piece::Pawn {
+        mv.notation = MoveNotation::San;
+    }
+    Ok(())
+}
+
+// "g1f3", "e7e8q": both squares and any promotion. the piece, and whether the move captures
+// or castles, are filled in once the move is found on the board
+pub fn parse_pgn_parse_uci_move(pair: Pair<Rule>, mv: &mut ChessMove) -> Result<(), PgnError> {
+    mv.notation = MoveNotation::Uci;
+    for inner in pair.into_inner() {
+        match inner.as_rule() {
+            Rule::location if mv.disambig.is_none() => {
+                mv.disambig = Some(Disambiguation::Square(parse_pgn_parse_square(inner)?));
+            }
+            Rule::location => {
+                parse_pgn_parse_location(inner, mv)?;
+            }
+            Rule::uci_promotion => {
+                let error = parse_pgn_pair_error(&inner, "a promotion piece");
+                let letter = parse_pgn_parse_getchar(inner)?.to_ascii_uppercase();
+                mv.promotion = Some(Piece::from_letter(letter).ok_or(error)?);
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+pub fn parse_pgn_parse_move_options(
+    move_pair: Pair<Rule>,
+    mv: &mut ChessMove,
+) -> Result<(), PgnError> {
+    let parsed = ChessParser::parse(Rule::options_util, move_pair.as_str())
+        .map_err(|_| parse_pgn_pair_error(&move_pair, "a target square"))?;
+    for out1 in parsed {
+        for out2 in out1.into_inner() {
+            match out2.as_rule() {
+                Rule::disambig => {
+                    mv.disambig = Some(parse_pgn_parse_disambig(out2)?);
+                }
+                Rule::capture => {
+                    mv.capture = true;
                 }
+                Rule::location => {
+                    parse_pgn_parse_location(out2, mv)?;
+                }
+                _ => {}
+            }
+        }
+    }
+    Ok(())
+}
+
+pub fn parse_pgn_parse_annotation(annotation_pair: Pair<Rule>, mv: &mut ChessMove) {
+    mv.annotation = annotation_pair.as_str().to_string();
+    for inner in annotation_pair.into_inner() {
+        match inner.as_rule() {
+            Rule::check_mark => {
+                mv.check = inner.as_str() == "+";
+                mv.mate = inner.as_str() == "#";
             }
+            Rule::move_quality => {
+                mv.quality = inner.as_str().to_string();
+            }
+            _ => {}
         }
     }
 }
 
-pub fn parse_pgn_parse_move(move_pair: Pair<Rule>) -> Option<ChessMove> {
-    let mut mv = ChessMove::default();
-    mv.full_str = move_pair.as_str().trim().to_string();
+pub fn parse_pgn_parse_move(move_pair: Pair<Rule>) -> Result<ChessMove, PgnError> {
+    let (line, column) = move_pair.line_col();
+    let mut mv = ChessMove {
+        line,
+        column,
+        ..Default::default()
+    };
 
     for inner in move_pair.into_inner() {
+        if inner.as_rule() != Rule::en_passant {
+            mv.full_str.push_str(inner.as_str().trim());
+        }
         match inner.as_rule() {
+            Rule::null_move => {
+                mv.null_move = true;
+            }
+            Rule::en_passant => {
+                mv.en_passant_suffix = true;
+            }
             Rule::pawn_capture => {
-                parse_pgn_parse_pawn_cap(inner, &mut mv);
+                parse_pgn_parse_pawn_cap(inner, &mut mv)?;
             }
             Rule::pawn_move => {
-                parse_pgn_parse_pawn_move(inner, &mut mv);
+                parse_pgn_parse_pawn_move(inner, &mut mv)?;
             }
             Rule::piece_move => {
-                parse_pgn_parse_piece(inner, &mut mv);
+                parse_pgn_parse_piece(inner, &mut mv)?;
+            }
+            Rule::long_move => {
+                parse_pgn_parse_long_move(inner, &mut mv)?;
+            }
+            Rule::uci_move => {
+                parse_pgn_parse_uci_move(inner, &mut mv)?;
             }
             Rule::special => {
                 mv.special = true;
+                mv.piece = Piece::King;
+                for side in inner.into_inner() {
+                    mv.castling = match side.as_rule() {
+                        Rule::castle_long => Some(CastlingSide::Queenside),
+                        _ => Some(CastlingSide::Kingside),
+                    };
+                }
             }
             Rule::annotation => {
-                mv.annotation = inner.as_str().to_string();
+                parse_pgn_parse_annotation(inner, &mut mv);
             }
             _ => {}
         }
     }
 
-    return Some(mv);
+    Ok(mv)
 }
 
-pub fn parse_pgn_parse_gameterm(
-    result: &mut String,
-    term_pair: Pair<Rule>,
-    turns: &mut Vec<ChessTurn>,
-) {
-    let mut has_halfturn = false;
-    let mut halfturn = ChessTurn::default();
+pub fn parse_pgn_parse_comment(comment_pair: Pair<Rule>) -> String {
+    let mut text = String::new();
+    for inner in comment_pair.into_inner() {
+        if matches!(inner.as_rule(), Rule::comment_text | Rule::line_comment) {
+            text = inner.as_str().trim().to_string();
+        }
+    }
+    text
+}
 
-    for pair in term_pair.into_inner() {
-        match pair.as_rule() {
-            Rule::result => {
-                *result = pair.as_str().to_string();
+pub fn parse_pgn_parse_nag(nag_pair: Pair<Rule>) -> Result<u8, PgnError> {
+    nag_pair
+        .as_str()
+        .trim_start_matches('$')
+        .parse()
+        .map_err(|_| parse_pgn_pair_error(&nag_pair, "a NAG from $0 to $255"))
+}
+
+// turns being assembled from movetext elements, either for the mainline or for a variation
+#[derive(Default)]
+pub struct TurnBuilder {
+    turns: Vec<ChessTurn>,
+    pending_comments: Vec<String>,
+    black_to_move: bool,
+    lenient: bool, // turns missing black's move are kept instead of rejected
+}
+
+impl TurnBuilder {
+    // the move played last in the turns parsed so far
+    fn last_move(&mut self) -> Option<&mut ChessMove> {
+        let turn = self.turns.last_mut()?;
+        match turn.black_move {
+            Some(_) => turn.black_move.as_mut(),
+            None => turn.white_move.as_mut(),
+        }
+    }
+}
+
+// adds a single movetext element (move number, move, NAG, comment or variation) to the turns parsed so far.
+// comments attach to the preceding move, or to the following one if no move precedes them in the turn.
+// variations are alternatives to the move they follow
+pub fn parse_pgn_parse_movetext(
+    builder: &mut TurnBuilder,
+    pair: Pair<Rule>,
+) -> Result<(), PgnError> {
+    match pair.as_rule() {
+        Rule::turn_number => {
+            // only the last turn of a game or variation may lack black's move
+            if !builder.lenient
+                && builder
+                    .turns
+                    .last()
+                    .is_some_and(|turn| turn.white_move.is_some() && turn.black_move.is_none())
+            {
+                return Err(parse_pgn_pair_error(&pair, "a move by black"));
             }
-            Rule::turn_number => {
-                has_halfturn = true;
-                halfturn.turn_number = parse_pgn_parse_turn_num(pair);
+            builder.turns.push(ChessTurn {
+                turn_number: parse_pgn_parse_turn_num(pair)?,
+                white_move: None,
+                black_move: None,
+            });
+            builder.black_to_move = false;
+        }
+        Rule::black_turn_number => {
+            let turn_number = parse_pgn_parse_turn_num(pair.clone())?;
+            // "12..." either resumes the current turn (after a comment or variation) or starts one with black
+            let resumes_turn = builder.turns.last().is_some_and(|turn| {
+                turn.turn_number == turn_number
+                    && turn.white_move.is_some()
+                    && turn.black_move.is_none()
+            });
+            // anywhere else than the start of the game or variation, it must resume the turn
+            if !resumes_turn && !builder.lenient {
+                if let Some(turn) = builder.turns.last() {
+                    let expected = if turn.black_move.is_none() {
+                        format!("\"{}...\"", turn.turn_number)
+                    } else {
+                        "a move by white".to_string()
+                    };
+                    return Err(parse_pgn_pair_error(&pair, &expected));
+                }
             }
-            Rule::chess_move => {
-                has_halfturn = true;
-                halfturn.white_move = parse_pgn_parse_move(pair);
+            if !resumes_turn {
+                builder.turns.push(ChessTurn {
+                    turn_number,
+                    white_move: None,
+                    black_move: None,
+                });
+            }
+            builder.black_to_move = true;
+        }
+        Rule::chess_move => {
+            let move_error = parse_pgn_pair_error(&pair, "a turn number before the move");
+            let mut mv = parse_pgn_parse_move(pair)?;
+            mv.pre_comments = std::mem::take(&mut builder.pending_comments);
+
+            // the grammar puts a turn number before any move
+            let turn = builder.turns.last_mut().ok_or(move_error)?;
+            if turn.white_move.is_none() && !builder.black_to_move {
+                turn.white_move = Some(mv);
+            } else {
+                turn.black_move = Some(mv);
+            }
+            builder.black_to_move = false;
+        }
+        Rule::comment => {
+            let comment = parse_pgn_parse_comment(pair);
+            match builder.last_move() {
+                Some(mv) => mv.comments.push(comment),
+                None => builder.pending_comments.push(comment),
+            }
+        }
+        Rule::nag => {
+            let nag = parse_pgn_parse_nag(pair)?;
+            if let Some(mv) = builder.last_move() {
+                mv.nags.push(nag);
+            }
+        }
+        Rule::chess_turn => parse_pgn_parse_turn(builder, pair)?,
+        Rule::variation => {
+            let variation = parse_variation(pair, builder.lenient)?;
+            if let Some(mv) = builder.last_move() {
+                mv.variations.push(variation);
             }
-            _ => {}
         }
+        _ => {}
     }
+    Ok(())
+}
+
+pub fn parse_pgn_parse_variation(variation_pair: Pair<Rule>) -> Result<ChessVariation, PgnError> {
+    parse_variation(variation_pair, false)
+}
+
+fn parse_variation(variation_pair: Pair<Rule>, lenient: bool) -> Result<ChessVariation, PgnError> {
+    let mut builder = TurnBuilder {
+        lenient,
+        ..TurnBuilder::default()
+    };
+    for pair in variation_pair.into_inner() {
+        parse_pgn_parse_movetext(&mut builder, pair)?;
+    }
+    Ok(ChessVariation {
+        turns: builder.turns,
+        comments: builder.pending_comments,
+    })
+}
 
-    if has_halfturn {
-        turns.push(halfturn);
+pub fn parse_pgn_parse_gameterm(result: &mut GameResult, term_pair: Pair<Rule>) {
+    for pair in term_pair.into_inner() {
+        if pair.as_rule() == Rule::result {
+            // the grammar only accepts valid results
+            *result = pair.as_str().parse().unwrap_or_default();
+        }
     }
 }
 
-pub fn parse_pgn_parse_metadata(metadata: &mut HashMap<String, String>, meta_pair: Pair<Rule>) {
+// adds a tag pair - a repeated tag is kept alongside the earlier one
+pub fn parse_pgn_parse_metadata(metadata: &mut Tags, meta_pair: Pair<Rule>) {
     let mut mkey = String::new();
     let mut mval: String = String::new();
 
-    for meta_pair in meta_pair.into_inner() {
+    // the value is nested in its quotes
+    for meta_pair in meta_pair.into_inner().flatten() {
         match meta_pair.as_rule() {
             Rule::meta_key => {
                 mkey = meta_pair.as_span().as_str().to_string();
             }
             Rule::meta_value => {
-                mval = meta_pair.as_span().as_str().to_string();
+                mval = unescape_tag_value(meta_pair.as_str());
             }
             _ => {}
         }
     }
-    metadata.insert(mkey, mval);
+    metadata.push(mkey, mval);
 }
 
-pub fn parse_pgn_parse_turn(turns: &mut Vec<ChessTurn>, turn_pair: Pair<Rule>) {
-    let mut turn = ChessTurn::default();
-    let mut i = 0;
-
+pub fn parse_pgn_parse_turn(
+    builder: &mut TurnBuilder,
+    turn_pair: Pair<Rule>,
+) -> Result<(), PgnError> {
     for pair in turn_pair.into_inner() {
-        match pair.as_rule() {
-            Rule::turn_number => {
-                turn.turn_number = parse_pgn_parse_turn_num(pair);
-            }
-            Rule::chess_move => {
-                if i == 1 {
-                    turn.white_move = parse_pgn_parse_move(pair)
-                } else {
-                    turn.black_move = parse_pgn_parse_move(pair)
-                }
+        parse_pgn_parse_movetext(builder, pair)?;
+    }
+    Ok(())
+}
+
+pub fn parse_pgn_parse_game(game_pair: Pair<Rule>) -> Result<ChessGame, PgnError> {
+    parse_game(game_pair, false)
+}
+
+fn parse_game(game_pair: Pair<Rule>, lenient: bool) -> Result<ChessGame, PgnError> {
+    let mut metadata = Tags::new();
+    let mut builder = TurnBuilder {
+        lenient,
+        ..TurnBuilder::default()
+    };
+    let mut result = GameResult::Unknown;
+
+    for info_pair in game_pair.into_inner() {
+        match info_pair.as_rule() {
+            Rule::game_term | Rule::fragment_term => {
+                parse_pgn_parse_gameterm(&mut result, info_pair)
             }
-            _ => {}
+            Rule::metadata => {
+                parse_pgn_parse_metadata(&mut metadata, info_pair);
+            }
+            _ => parse_pgn_parse_movetext(&mut builder, info_pair)?,
         }
+    }
 
-        i += 1;
+    // a game ending mid-turn still gets an (empty) black move
+    if let Some(turn) = builder.turns.last_mut() {
+        if turn.white_move.is_some() {
+            turn.black_move.get_or_insert_with(ChessMove::default);
+        }
     }
 
-    turns.push(turn);
+    Ok(ChessGame {
+        metadata,
+        turns: builder.turns,
+        comments: builder.pending_comments,
+        result,
+    })
 }
 
-pub fn parse_pgn(pgn_text: &str) -> Result<ChessGame, Error<Rule>> {
-    let text_breakless = pgn_text.split_whitespace().collect::<Vec<&str>>().join(" ");
+pub fn parse_pgn(pgn_text: &str) -> Result<ChessGame, PgnError> {
+    parse_pgn_with_options(pgn_text, &ParseOptions::default())
+}
+
+pub fn parse_pgn_with_options(
+    pgn_text: &str,
+    options: &ParseOptions,
+) -> Result<ChessGame, PgnError> {
+    parse_pgn_text(pgn_text, options, false)
+}
 
-    let mut metadata = std::collections::HashMap::new();
-    let mut turns = Vec::new();
-    let mut result = String::new();
+// parses a single game. in lenient mode, turns may lack black's move anywhere
+// (after a move was skipped), not just at the end
+pub(crate) fn parse_pgn_text(
+    pgn_text: &str,
+    options: &ParseOptions,
+    lenient: bool,
+) -> Result<ChessGame, PgnError> {
+    let rule = if options.require_result {
+        Rule::chess_game
+    } else {
+        Rule::chess_fragment
+    };
 
-    let res = ChessParser::parse(Rule::chess_game, &text_breakless);
+    let english;
+    let pgn_text = if options.piece_letters == PieceLetters::ENGLISH {
+        pgn_text
+    } else {
+        english = options.piece_letters.english_text(pgn_text);
+        english.as_str()
+    };
 
-    if res.is_err() {
-        return Err(res.err().unwrap());
+    check_variation_depth(pgn_text)?;
+    let mut parsed =
+        ChessParser::parse(rule, pgn_text).map_err(|error| PgnError::from_pest(error, pgn_text))?;
+    let game_pair = parsed.next().ok_or(PgnError::UnexpectedEnd {
+        line: 1,
+        column: 1,
+        expected: "a game".to_string(),
+    })?;
+    let fen_position = tag_position(game_pair.clone(), "FEN");
+    let mut game = parse_game(game_pair, lenient)?;
+    let replay_error = |error: ReplayError| match PgnError::from(error) {
+        // point at the FEN tag itself
+        PgnError::BadTag { token, reason, .. } => PgnError::BadTag {
+            line: fen_position.0,
+            column: fen_position.1,
+            token,
+            reason,
+        },
+        error => error,
+    };
+
+    if let Some(notation) = options.notation {
+        check_notation(&game.turns, notation)?;
+    }
+    resolve_uci_moves(&mut game).map_err(replay_error)?;
+
+    if options.validate_moves {
+        replay_game(&game).map_err(replay_error)?;
+        // variations aren't validated, so a bad one only stops the marking
+        let _ = game.mark_en_passant();
     }
 
-    match res {
-        Ok(parsed) => {
-            for pair in parsed {
-                match pair.as_rule() {
-                    Rule::chess_game => {
-                        for info_pair in pair.into_inner() {
-                            match info_pair.as_rule() {
-                                Rule::game_term => {
-                                    parse_pgn_parse_gameterm(&mut result, info_pair, &mut turns)
-                                }
-                                Rule::metadata => {
-                                    parse_pgn_parse_metadata(&mut metadata, info_pair);
-                                }
-                                Rule::chess_turn => {
-                                    parse_pgn_parse_turn(&mut turns, info_pair);
-                                }
-                                _ => {}
-                            }
-                        }
+    Ok(game)
+}
+
+// deepest variation nesting accepted - the grammar and the parser recurse for every level,
+// so deeper input could overflow the stack
+pub const MAX_VARIATION_DEPTH: usize = 32;
+
+// rejects variations nested deeper than MAX_VARIATION_DEPTH before the grammar runs.
+// parentheses inside comments and tags don't count
+fn check_variation_depth(pgn_text: &str) -> Result<(), PgnError> {
+    let mut depth = 0;
+    let (mut line, mut column) = (1, 0);
+    let mut scanner = PgnScanner::default();
+
+    for ch in pgn_text.chars() {
+        column += 1;
+        if scanner.kind(ch) == TextKind::Movetext {
+            match ch {
+                '(' => {
+                    depth += 1;
+                    if depth > MAX_VARIATION_DEPTH {
+                        return Err(PgnError::Syntax {
+                            line,
+                            column,
+                            token: "(".to_string(),
+                            expected: format!("at most {} nested variations", MAX_VARIATION_DEPTH),
+                        });
                     }
-                    _ => {}
                 }
+                ')' => depth = depth.saturating_sub(1),
+                _ => {}
             }
         }
-        Err(_) => {}
+        if ch == '\n' {
+            line += 1;
+            column = 0;
+        }
     }
 
-    Ok(ChessGame {
-        metadata,
-        turns,
-        result,
-    })
+    Ok(())
+}
+
+// line and column of the tag pair with the given key, (0, 0) if the game doesn't have it
+fn tag_position(game_pair: Pair<Rule>, key: &str) -> (usize, usize) {
+    game_pair
+        .into_inner()
+        .filter(|pair| pair.as_rule() == Rule::metadata)
+        .find(|pair| {
+            pair.clone()
+                .into_inner()
+                .any(|inner| inner.as_rule() == Rule::meta_key && inner.as_str() == key)
+        })
+        .map_or((0, 0), |pair| pair.line_col())
+}
+
+// parses a game whose text starts at `offset` in the original input, adjusting the positions to match
+pub(crate) fn parse_pgn_at_offset(
+    pgn_text: &str,
+    options: &ParseOptions,
+    offset: SourceOffset,
+) -> Result<ChessGame, PgnError> {
+    match parse_pgn_with_options(pgn_text, options) {
+        Ok(mut game) => {
+            offset.apply_to_turns(&mut game.turns);
+            Ok(game)
+        }
+        Err(error) => Err(error.shifted(offset)),
+    }
+}
+
+// splits a PGN database into the text of its individual games.
+// a game ends after its result token, or when a new tag section starts after movetext
+// (so a game missing its result doesn't swallow the next one)
+pub fn split_pgn_games(pgn_text: &str) -> Vec<&str> {
+    let mut games = Vec::new();
+    let mut start = 0;
+
+    for boundary in GameSplitter::default().scan(pgn_text) {
+        games.push(&pgn_text[start..boundary]);
+        start = boundary;
+    }
+    games.push(&pgn_text[start..]);
+
+    games
+        .into_iter()
+        .filter(|game| !game.trim().is_empty())
+        .collect()
+}
+
+// parses every game of a PGN database separately, so a malformed game doesn't reject the others
+pub fn parse_pgn_database(pgn_text: &str) -> Vec<Result<ChessGame, PgnError>> {
+    parse_pgn_database_with_options(pgn_text, &ParseOptions::default())
+}
+
+pub fn parse_pgn_database_with_options(
+    pgn_text: &str,
+    options: &ParseOptions,
+) -> Vec<Result<ChessGame, PgnError>> {
+    split_pgn_games(pgn_text)
+        .into_iter()
+        .map(|game_text| {
+            parse_pgn_at_offset(
+                game_text,
+                options,
+                SourceOffset::of_slice(pgn_text, game_text),
+            )
+        })
+        .collect()
 }
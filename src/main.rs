@@ -1,7 +1,10 @@
+use chess_parser::{
+    replay_positions, ChessGame, Diagnostic, ParseOptions, PgnReader, Piece, PieceLetters,
+};
 use clap::{Arg, ArgAction, Command};
 use std::collections::HashMap;
-use std::fs;
-use std::io;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
 
 fn main() -> io::Result<()> {
     // Define the CLI using clap
@@ -12,15 +15,34 @@ fn main() -> io::Result<()> {
         .subcommand_required(true)
         .arg_required_else_help(true)
         .subcommand(
-            Command::new("parse").about("Parses a PGN file.").arg(
-                Arg::new("file")
-                    .short('f')
-                    .long("file")
-                    .value_name("FILENAME")
-                    .help("Specifies the PGN file to parse.")
-                    .required(true)
-                    .action(ArgAction::Set),
-            ),
+            Command::new("parse")
+                .about("Parses a PGN file.")
+                .arg(
+                    Arg::new("file")
+                        .short('f')
+                        .long("file")
+                        .value_name("FILENAME")
+                        .help("Specifies the PGN file to parse ('-' reads from stdin).")
+                        .required(true)
+                        .action(ArgAction::Set),
+                )
+                .arg(
+                    Arg::new("lenient")
+                        .long("lenient")
+                        .help(
+                            "Skips unparseable tokens and games, then reports every problem found.",
+                        )
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("piece-letters")
+                        .long("piece-letters")
+                        .value_name("LETTERS")
+                        .help("Piece letters of the moves: english, german, french or figurine.")
+                        .value_parser(|letters: &str| letters.parse::<PieceLetters>())
+                        .default_value("english")
+                        .action(ArgAction::Set),
+                ),
         )
         .subcommand(Command::new("credits").about("Displays credits information."))
         .get_matches();
@@ -30,145 +52,76 @@ fn main() -> io::Result<()> {
             // Handle the 'parse' command
             let filename = sub_m.get_one::<String>("file").unwrap();
 
-            // Read the PGN file
-            let contents = match fs::read_to_string(filename) {
-                Ok(c) => c,
-                Err(error) => {
-                    eprintln!("Error reading file '{}': {}", filename, error);
-                    return Ok(());
+            // Open the PGN file, or stdin for '-'
+            let input: Box<dyn BufRead> = if filename == "-" {
+                Box::new(io::stdin().lock())
+            } else {
+                match File::open(filename) {
+                    Ok(file) => Box::new(BufReader::new(file)),
+                    Err(error) => {
+                        eprintln!("Error reading file '{}': {}", filename, error);
+                        return Ok(());
+                    }
                 }
             };
 
-            match chess_parser::parse_pgn(&contents) {
-                Ok(game) => {
-                    println!("VALID PGN GAME");
-                    println!("Result: {}\n", game.result);
-
-                    println!("Metadata length: {}", game.metadata.len());
-                    for (key, value) in &game.metadata {
-                        println!("Meta || {} : {}", key, value);
-                    }
-                    println!();
-
-                    println!("Turn count: {}", game.turns.len());
-                    for turn in &game.turns {
-                        print!("Turn {}:\t", turn.turn_number);
-                        if let Some(white_move) = &turn.white_move {
-                            print!("white: {}\t", white_move.full_str);
-                        } else {
-                            print!("white: None\t");
-                        }
-                        if let Some(black_move) = &turn.black_move {
-                            println!("black: {}", black_move.full_str);
-                        } else {
-                            println!("black: None");
-                        }
-                    }
-                    println!();
-
-                    // Example of analysis that can be done with parsed data
-                    // Identify the opening based on the first few moves
-                    let mut opening_moves = Vec::new();
-                    for turn in &game.turns {
-                        if let Some(white_move) = &turn.white_move {
-                            opening_moves.push(white_move.full_str.clone());
-                        }
-                        if let Some(black_move) = &turn.black_move {
-                            opening_moves.push(black_move.full_str.clone());
+            // Games are read and analyzed one at a time, so huge databases don't need to fit in memory
+            let mut game_count = 0;
+            let mut valid_count = 0;
+            let options = ParseOptions {
+                piece_letters: *sub_m.get_one::<PieceLetters>("piece-letters").unwrap(),
+                ..ParseOptions::default()
+            };
+            let mut reader = PgnReader::with_options(input, options);
+            if sub_m.get_flag("lenient") {
+                let mut diagnostics: Vec<Diagnostic> = Vec::new();
+                while let Some(game_result) = reader.next_game_lenient() {
+                    game_count += 1;
+                    println!("=== Game {} ===", game_count);
+                    match game_result {
+                        Ok((game, game_diagnostics)) => {
+                            if let Some(game) = game {
+                                valid_count += 1;
+                                print_game_analysis(&game);
+                            }
+                            diagnostics.extend(game_diagnostics);
                         }
-                        if opening_moves.len() >= 6 {
-                            break; // Analyze the first 6 moves (3 turns)
+                        Err(error) => {
+                            eprintln!("Read error: {}", error);
                         }
                     }
+                }
 
-                    // Concatenate the moves into a single string
-                    let opening_sequence = opening_moves.join(" ");
-
-                    // A simple mapping of opening sequences to opening names
-                    let openings = vec![
-                        ("e4 e5 Nf3 Nc6 Bb5 a6", "Ruy Lopez"),
-                        ("e4 c5", "Sicilian Defense"),
-                        ("d4 d5 c4", "Queen's Gambit"),
-                        // etc
-                    ];
-                    // Identify the opening
-                    let opening_name = openings.iter().find_map(|(sequence, name)| {
-                        if opening_sequence.starts_with(sequence) {
-                            Some(*name)
-                        } else {
-                            None
-                        }
-                    });
-
-                    if let Some(name) = opening_name {
-                        println!("Identified Opening: {}\n", name);
-                    } else {
-                        println!("Opening not recognized.\n");
+                if !diagnostics.is_empty() {
+                    eprintln!("{} problems found:", diagnostics.len());
+                    for diagnostic in &diagnostics {
+                        eprintln!("  {}", diagnostic);
                     }
-
-                    // Analyze piece activity
-                    let mut piece_activity: HashMap<char, usize> = HashMap::new();
-
-                    for turn in &game.turns {
-                        for chess_move in [&turn.white_move, &turn.black_move] {
-                            if let Some(mv) = chess_move {
-                                let piece = mv.piece.to_ascii_uppercase();
-                                *piece_activity.entry(piece).or_insert(0) += 1;
-                            }
+                }
+            } else {
+                for game_result in reader {
+                    game_count += 1;
+                    println!("=== Game {} ===", game_count);
+                    match game_result {
+                        Ok(game) => {
+                            valid_count += 1;
+                            print_game_analysis(&game);
                         }
-                    }
-                    println!("Piece Activity:");
-                    for (piece, count) in &piece_activity {
-                        let piece_name = match piece {
-                            'P' => "Pawn",
-                            'N' => "Knight",
-                            'B' => "Bishop",
-                            'R' => "Rook",
-                            'Q' => "Queen",
-                            'K' => "King",
-                            _ => "Unknown",
-                        };
-                        println!("Piece: {:<6} Moves: {}", piece_name, count);
-                    }
-                    println!();
-
-                    // Calculate material balance over time
-                    let mut white_material = 39; // Initial material value
-                    let mut black_material = 39;
-
-                    let mut material_balance_over_time = Vec::new();
-
-                    for turn in &game.turns {
-                        for (player, chess_move) in
-                            [("white", &turn.white_move), ("black", &turn.black_move)]
-                        {
-                            if let Some(mv) = chess_move {
-                                if mv.capture {
-                                    // For demonstration, subtract an average piece value
-                                    let average_piece_value = 1;
-                                    if player == "white" {
-                                        black_material -= average_piece_value;
-                                    } else {
-                                        white_material -= average_piece_value;
-                                    }
-                                }
-                            }
+                        Err(error) => {
+                            eprintln!("Parsing error: {}", error);
                         }
-
-                        let balance = white_material as isize - black_material as isize;
-                        material_balance_over_time.push(balance);
                     }
-
-                    println!("Material Balance Over Time (White - Black):");
-                    for (i, balance) in material_balance_over_time.iter().enumerate() {
-                        println!("After turn {}: {}", i + 1, balance);
-                    }
-                    println!();
-                }
-                Err(error) => {
-                    eprintln!("Parsing error: {}", error);
                 }
             }
+
+            if game_count == 0 {
+                eprintln!("No games found in '{}'", filename);
+            } else {
+                println!(
+                    "Parsed {} of {} games successfully.",
+                    valid_count, game_count
+                );
+            }
         }
         Some(("credits", _)) => {
             // Handle the 'credits' command
@@ -187,3 +140,147 @@ fn main() -> io::Result<()> {
 
     Ok(())
 }
+
+fn print_game_analysis(game: &ChessGame) {
+    println!("VALID PGN GAME");
+    println!("Result: {}\n", game.result);
+
+    println!("Metadata length: {}", game.metadata.len());
+    for (key, value) in &game.metadata {
+        println!("Meta || {} : {}", key, value);
+    }
+    for key in game.metadata.duplicates() {
+        println!("Warning: tag '{}' appears more than once", key);
+    }
+    for issue in game.check_result() {
+        println!("Warning: {}", issue);
+    }
+    for issue in game.verify_check_marks().unwrap_or_default() {
+        println!("Warning: {}", issue);
+    }
+    println!();
+
+    println!("Turn count: {}", game.turns.len());
+    for turn in &game.turns {
+        print!("Turn {}:\t", turn.turn_number);
+        if let Some(white_move) = &turn.white_move {
+            print!("white: {}\t", white_move.full_str);
+        } else {
+            print!("white: None\t");
+        }
+        if let Some(black_move) = turn.black_move.as_ref().filter(|mv| mv.is_played()) {
+            println!("black: {}", black_move.full_str);
+        } else {
+            println!("black: None");
+        }
+    }
+    println!();
+
+    // Replay the moves on a board to check that they are legal
+    match replay_positions(game) {
+        Ok(positions) => {
+            println!("All moves are legal.");
+            if let Some(last) = positions.last() {
+                println!("Final position: {}\n", last.to_fen());
+            }
+        }
+        Err(error) => println!("Replay failed: {}\n", error),
+    }
+
+    // Example of analysis that can be done with parsed data
+    // Identify the opening based on the first few moves
+    let mut opening_moves = Vec::new();
+    for turn in &game.turns {
+        if let Some(white_move) = &turn.white_move {
+            opening_moves.push(white_move.full_str.clone());
+        }
+        if let Some(black_move) = turn.black_move.as_ref().filter(|mv| mv.is_played()) {
+            opening_moves.push(black_move.full_str.clone());
+        }
+        if opening_moves.len() >= 6 {
+            break; // Analyze the first 6 moves (3 turns)
+        }
+    }
+
+    // Concatenate the moves into a single string
+    let opening_sequence = opening_moves.join(" ");
+
+    // A simple mapping of opening sequences to opening names
+    let openings = [
+        ("e4 e5 Nf3 Nc6 Bb5 a6", "Ruy Lopez"),
+        ("e4 c5", "Sicilian Defense"),
+        ("d4 d5 c4", "Queen's Gambit"),
+        // etc
+    ];
+    // Identify the opening
+    let opening_name = openings.iter().find_map(|(sequence, name)| {
+        if opening_sequence.starts_with(sequence) {
+            Some(*name)
+        } else {
+            None
+        }
+    });
+
+    if let Some(name) = opening_name {
+        println!("Identified Opening: {}\n", name);
+    } else {
+        println!("Opening not recognized.\n");
+    }
+
+    // Analyze piece activity
+    let mut piece_activity: HashMap<Piece, usize> = HashMap::new();
+
+    for turn in &game.turns {
+        for mv in [&turn.white_move, &turn.black_move]
+            .into_iter()
+            .flatten()
+            .filter(|mv| mv.is_played() && !mv.null_move)
+        {
+            *piece_activity.entry(mv.piece).or_insert(0) += 1;
+        }
+    }
+    println!("Piece Activity:");
+    for (piece, count) in &piece_activity {
+        let piece_name = match piece {
+            Piece::Pawn => "Pawn",
+            Piece::Knight => "Knight",
+            Piece::Bishop => "Bishop",
+            Piece::Rook => "Rook",
+            Piece::Queen => "Queen",
+            Piece::King => "King",
+        };
+        println!("Piece: {:<6} Moves: {}", piece_name, count);
+    }
+    println!();
+
+    // Calculate material balance over time
+    let mut white_material = 39; // Initial material value
+    let mut black_material = 39;
+
+    let mut material_balance_over_time = Vec::new();
+
+    for turn in &game.turns {
+        for (player, chess_move) in [("white", &turn.white_move), ("black", &turn.black_move)] {
+            if let Some(mv) = chess_move {
+                if mv.capture {
+                    // For demonstration, subtract an average piece value
+                    let average_piece_value = 1;
+                    if player == "white" {
+                        black_material -= average_piece_value;
+                    } else {
+                        white_material -= average_piece_value;
+                    }
+                }
+            }
+        }
+
+        let balance = white_material as isize - black_material as isize;
+        material_balance_over_time.push(balance);
+    }
+
+    println!("Material Balance Over Time (White - Black):");
+    for (i, balance) in material_balance_over_time.iter().enumerate() {
+        println!("After turn {}: {}", i + 1, balance);
+    }
+    println!();
+}
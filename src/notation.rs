@@ -0,0 +1,102 @@
+// moves written as coordinates: long algebraic and UCI input, UCI output
+
+use crate::board::{replay_lines, replay_start};
+use crate::types::{CastlingSide, Disambiguation};
+use crate::{
+    replay_game, ChessGame, ChessMove, ChessTurn, MoveNotation, PgnError, PlayedMove, ReplayError,
+};
+
+// rejects the first move (variations included) written in a notation other than `notation`.
+// castling and piece captures from a named square ("Qh4xe1") are spelled the same in SAN and
+// long algebraic notation, and null moves in all of them
+pub(crate) fn check_notation(turns: &[ChessTurn], notation: MoveNotation) -> Result<(), PgnError> {
+    let long_san = |mv: &ChessMove| {
+        mv.castling.is_some()
+            || (mv.capture && matches!(mv.disambig, Some(Disambiguation::Square(_))))
+    };
+    let allowed = |mv: &ChessMove| {
+        mv.notation == notation
+            || mv.null_move
+            || (notation == MoveNotation::Long && mv.notation == MoveNotation::San && long_san(mv))
+    };
+    match find_move(turns, &|mv| !allowed(mv)) {
+        Some(mv) => Err(PgnError::Syntax {
+            line: mv.line,
+            column: mv.column,
+            token: mv.full_str.clone(),
+            expected: format!("a move in {} notation", notation),
+        }),
+        None => Ok(()),
+    }
+}
+
+fn find_move<'a>(
+    turns: &'a [ChessTurn],
+    found: &impl Fn(&ChessMove) -> bool,
+) -> Option<&'a ChessMove> {
+    for mv in turns
+        .iter()
+        .flat_map(|turn| [&turn.white_move, &turn.black_move])
+        .flatten()
+    {
+        if found(mv) {
+            return Some(mv);
+        }
+        for variation in &mv.variations {
+            if let Some(mv) = find_move(&variation.turns, found) {
+                return Some(mv);
+            }
+        }
+    }
+    None
+}
+
+// fills in the piece of every UCI move, and whether it captures or castles, from the board
+pub(crate) fn resolve_uci_moves(game: &mut ChessGame) -> Result<(), ReplayError> {
+    if find_move(&game.turns, &|mv| mv.notation == MoveNotation::Uci).is_none() {
+        return Ok(());
+    }
+
+    let mut board = replay_start(game)?;
+    replay_lines(&mut board, &mut game.turns, &mut |_, played, mv| {
+        if mv.notation != MoveNotation::Uci {
+            return;
+        }
+        mv.piece = played.piece;
+        mv.capture = played.captured.is_some();
+        mv.en_passant = played.en_passant;
+        if played.castling {
+            // castling has no target square, like "O-O"
+            mv.special = true;
+            mv.castling = Some(if played.to.file.index() == 6 {
+                CastlingSide::Kingside
+            } else {
+                CastlingSide::Queenside
+            });
+            mv.disambig = None;
+            mv.loc_col = None;
+            mv.loc_row = None;
+        }
+    })
+}
+
+impl PlayedMove {
+    // the move in UCI notation, e.g. "e2e4", "e1g1" for castling, "e7e8q" or "0000" for a null move
+    pub fn uci(&self) -> String {
+        if self.null {
+            return "0000".to_string();
+        }
+        let promotion = self
+            .promotion
+            .map(|piece| piece.letter().to_ascii_lowercase().to_string())
+            .unwrap_or_default();
+        format!("{}{}{}", self.from, self.to, promotion)
+    }
+}
+
+impl ChessGame {
+    // the main line as UCI moves, ready for a "position ... moves" engine command
+    pub fn uci_moves(&self) -> Result<Vec<String>, ReplayError> {
+        Ok(replay_game(self)?.iter().map(PlayedMove::uci).collect())
+    }
+}
@@ -0,0 +1,163 @@
+use crate::error::SourceOffset;
+use crate::lenient::{parse_lenient_at_offset, Diagnostic};
+use crate::scanner::{PgnScanner, TextKind};
+use crate::{parse_pgn_at_offset, ChessGame, ParseOptions, PgnError};
+use std::collections::VecDeque;
+use std::io::{self, BufRead};
+
+// game results that close a game's movetext
+const GAME_RESULTS: [&str; 4] = ["1-0", "0-1", "1/2-1/2", "*"];
+
+// finds game boundaries in PGN text fed to it piece by piece.
+// every piece must end at a whitespace boundary (whole lines are fine)
+#[derive(Default)]
+pub(crate) struct GameSplitter {
+    scanner: PgnScanner,
+    seen_movetext: bool,
+    in_tag: bool, // the last character read was part of a tag
+}
+
+impl GameSplitter {
+    // returns the byte offsets in `text` where a new game starts
+    pub(crate) fn scan(&mut self, text: &str) -> Vec<usize> {
+        let mut boundaries = Vec::new();
+        let mut token_start = None; // of the movetext token being read
+
+        for (i, ch) in text.char_indices() {
+            let kind = self.scanner.kind(ch);
+            if kind == TextKind::Movetext && !ch.is_whitespace() {
+                token_start.get_or_insert(i);
+                continue;
+            }
+            if let Some(start) = token_start.take() {
+                self.read_token(&text[start..i], i, &mut boundaries);
+            }
+
+            // a tag after movetext starts the next game, as does one after an unclosed
+            // comment or tag value
+            let starts_tag = kind == TextKind::Tag && !self.in_tag;
+            if (starts_tag && self.seen_movetext) || self.scanner.recovered() {
+                boundaries.push(i);
+                self.seen_movetext = false;
+            }
+            self.in_tag = kind == TextKind::Tag;
+        }
+        if let Some(start) = token_start {
+            self.read_token(&text[start..], text.len(), &mut boundaries);
+        }
+
+        boundaries
+    }
+
+    // a whole movetext token, ending at `end`. a result ends the game
+    fn read_token(&mut self, token: &str, end: usize, boundaries: &mut Vec<usize>) {
+        self.seen_movetext = !GAME_RESULTS.contains(&token);
+        if !self.seen_movetext {
+            boundaries.push(end);
+        }
+    }
+}
+
+// reads games one at a time from a PGN stream, only keeping the current game in memory
+pub struct PgnReader<R: BufRead> {
+    input: R,
+    options: ParseOptions,
+    splitter: GameSplitter,
+    bytes: Vec<u8>,
+    line: String,
+    lines_read: usize,
+    current: String,
+    current_offset: SourceOffset, // where the current game starts in the input
+    ready: VecDeque<(String, SourceOffset)>,
+    finished: bool,
+}
+
+impl<R: BufRead> PgnReader<R> {
+    pub fn new(input: R) -> Self {
+        Self::with_options(input, ParseOptions::default())
+    }
+
+    pub fn with_options(input: R, options: ParseOptions) -> Self {
+        PgnReader {
+            input,
+            options,
+            splitter: GameSplitter::default(),
+            bytes: Vec::new(),
+            line: String::new(),
+            lines_read: 0,
+            current: String::new(),
+            current_offset: SourceOffset::default(),
+            ready: VecDeque::new(),
+            finished: false,
+        }
+    }
+
+    // next game's raw text, without parsing it
+    pub fn next_game_text(&mut self) -> Option<io::Result<String>> {
+        self.next_game_chunk()
+            .map(|chunk| chunk.map(|(text, _)| text))
+    }
+
+    // next game parsed leniently, with the problems skipped in it.
+    // the game is None when nothing could be recovered from it
+    pub fn next_game_lenient(
+        &mut self,
+    ) -> Option<io::Result<(Option<ChessGame>, Vec<Diagnostic>)>> {
+        self.next_game_chunk().map(|chunk| {
+            chunk.map(|(text, offset)| parse_lenient_at_offset(&text, &self.options, offset))
+        })
+    }
+
+    fn next_game_chunk(&mut self) -> Option<io::Result<(String, SourceOffset)>> {
+        while self.ready.is_empty() && !self.finished {
+            self.bytes.clear();
+            match self.input.read_until(b'\n', &mut self.bytes) {
+                Ok(0) => {
+                    self.finished = true;
+                    self.finish_game();
+                }
+                Ok(_) => {
+                    // bytes that aren't UTF-8 (e.g. Latin-1 names) become U+FFFD instead of
+                    // failing the rest of the stream
+                    self.line = String::from_utf8_lossy(&self.bytes).into_owned();
+                    let mut start = 0;
+                    for boundary in self.splitter.scan(&self.line) {
+                        self.current.push_str(&self.line[start..boundary]);
+                        self.finish_game();
+                        self.current_offset = SourceOffset {
+                            lines: self.lines_read,
+                            columns: self.line[..boundary].chars().count(),
+                        };
+                        start = boundary;
+                    }
+                    self.current.push_str(&self.line[start..]);
+                    self.lines_read += 1;
+                }
+                Err(error) => {
+                    self.finished = true;
+                    return Some(Err(error));
+                }
+            }
+        }
+
+        self.ready.pop_front().map(Ok)
+    }
+
+    fn finish_game(&mut self) {
+        let game = std::mem::take(&mut self.current);
+        if !game.trim().is_empty() {
+            self.ready.push_back((game, self.current_offset));
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for PgnReader<R> {
+    type Item = Result<ChessGame, PgnError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.next_game_chunk()? {
+            Ok((text, offset)) => Some(parse_pgn_at_offset(&text, &self.options, offset)),
+            Err(error) => Some(Err(PgnError::Io(error))),
+        }
+    }
+}
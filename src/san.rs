@@ -0,0 +1,117 @@
+// canonical Standard Algebraic Notation: the shortest spelling of a move that identifies it,
+// with the check or mate suffix the position calls for
+
+use crate::board::{replay_lines, replay_start};
+use crate::parser::{ChessParser, Rule};
+use crate::types::{CastlingSide, Piece};
+use crate::{parse_pgn_parse_move, Board, ChessGame, ChessMove, PlayedMove, ReplayError};
+use pest::Parser;
+
+impl Board {
+    // canonical SAN of a legal move in this position, e.g. "Nbd2", "exd5", "e8=Q+" or "O-O-O#"
+    pub fn san(&self, mv: &PlayedMove) -> String {
+        // passing the turn can't give check
+        if mv.null {
+            return "--".to_string();
+        }
+        let mut san = String::new();
+
+        if mv.castling {
+            let side = if mv.to.file.index() == 6 {
+                CastlingSide::Kingside
+            } else {
+                CastlingSide::Queenside
+            };
+            san.push_str(&side.to_string());
+        } else {
+            if mv.piece == Piece::Pawn {
+                // a capturing pawn is named by its file
+                if mv.captured.is_some() {
+                    san.push(mv.from.file.to_char());
+                }
+            } else {
+                san.push(mv.piece.letter());
+                san.push_str(&self.disambiguation(mv));
+            }
+            if mv.captured.is_some() {
+                san.push('x');
+            }
+            san.push_str(&mv.to.to_string());
+            if let Some(promotion) = mv.promotion {
+                san.push('=');
+                san.push(promotion.letter());
+            }
+        }
+
+        let mut after = self.clone();
+        after.make_move(mv);
+        if after.is_checkmate() {
+            san.push('#');
+        } else if after.is_check() {
+            san.push('+');
+        }
+        san
+    }
+
+    // as little of the origin square as tells the move apart from other pieces of the same kind
+    // going to the same square: the file if it is enough, else the rank, else both
+    fn disambiguation(&self, mv: &PlayedMove) -> String {
+        let rivals: Vec<PlayedMove> = self
+            .legal_moves()
+            .into_iter()
+            .filter(|other| other.piece == mv.piece && other.to == mv.to && other.from != mv.from)
+            .collect();
+
+        if rivals.is_empty() {
+            String::new()
+        } else if rivals.iter().all(|other| other.from.file != mv.from.file) {
+            mv.from.file.to_string()
+        } else if rivals.iter().all(|other| other.from.rank != mv.from.rank) {
+            mv.from.rank.to_string()
+        } else {
+            mv.from.to_string()
+        }
+    }
+}
+
+impl ChessGame {
+    // rewrites every move, variations included, into canonical SAN, keeping move quality glyphs,
+    // comments and NAGs. returns how many moves changed.
+    // fails, leaving the game partly rewritten, when a move can't be replayed
+    pub fn normalize_san(&mut self) -> Result<usize, ReplayError> {
+        let mut board = replay_start(self)?;
+        let mut changed = 0;
+        replay_lines(&mut board, &mut self.turns, &mut |before, played, mv| {
+            let san = before.san(played) + &mv.quality;
+            if mv.full_str != san {
+                rewrite_move(mv, &san);
+                changed += 1;
+            }
+        })?;
+        Ok(changed)
+    }
+}
+
+// replaces the notation of a move, keeping what was attached to it
+fn rewrite_move(mv: &mut ChessMove, san: &str) {
+    // canonical SAN is always valid move text
+    let Some(pair) = ChessParser::parse(Rule::chess_move, san)
+        .ok()
+        .and_then(|mut pairs| pairs.next())
+    else {
+        return;
+    };
+    let Ok(rewritten) = parse_pgn_parse_move(pair) else {
+        return;
+    };
+    *mv = ChessMove {
+        comments: std::mem::take(&mut mv.comments),
+        pre_comments: std::mem::take(&mut mv.pre_comments),
+        nags: std::mem::take(&mut mv.nags),
+        variations: std::mem::take(&mut mv.variations),
+        en_passant: mv.en_passant,
+        line: mv.line,
+        column: mv.column,
+        ..rewritten
+    };
+}
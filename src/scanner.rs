@@ -0,0 +1,88 @@
+// the part of PGN text each character belongs to, for the passes that look at the raw text
+// before (or instead of) the grammar: splitting databases, translating piece letters and
+// limiting variation depth. they all share it, so tags, quotes and comments are read one way
+
+// what a character is part of. the brackets, quotes and braces belong to what they enclose
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum TextKind {
+    Movetext,
+    Tag,
+    Comment,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+enum State {
+    #[default]
+    Movetext,
+    Tag,
+    TagValue,
+    BraceComment,
+    LineComment,
+}
+
+// reads PGN text one character at a time. the text may be fed in pieces.
+// a tag at the start of a line after a blank line always starts a tag, so a comment or tag
+// value that is never closed only swallows the rest of its own game
+#[derive(Debug, Clone, Default)]
+pub(crate) struct PgnScanner {
+    state: State,
+    escaped: bool,    // after a backslash in a tag value
+    line_ends: usize, // line ends since the last character that isn't whitespace
+    line_start: bool, // the last character ended a line
+    recovered: bool,  // the last character closed an unterminated comment or tag value
+}
+
+impl PgnScanner {
+    pub(crate) fn kind(&mut self, ch: char) -> TextKind {
+        self.recovered = ch == '['
+            && self.line_start
+            && self.line_ends >= 2
+            && matches!(
+                self.state,
+                State::Tag | State::TagValue | State::BraceComment
+            );
+        if self.recovered {
+            self.state = State::Movetext;
+            self.escaped = false;
+        }
+        if ch == '\n' {
+            self.line_ends += 1;
+        } else if !ch.is_whitespace() {
+            self.line_ends = 0;
+        }
+        self.line_start = ch == '\n';
+
+        let (kind, state) = match self.state {
+            State::Movetext => match ch {
+                '[' => (TextKind::Tag, State::Tag),
+                '{' => (TextKind::Comment, State::BraceComment),
+                ';' => (TextKind::Comment, State::LineComment),
+                _ => (TextKind::Movetext, State::Movetext),
+            },
+            State::Tag => match ch {
+                '"' => (TextKind::Tag, State::TagValue),
+                ']' => (TextKind::Tag, State::Movetext),
+                _ => (TextKind::Tag, State::Tag),
+            },
+            State::TagValue => {
+                let closed = !self.escaped && ch == '"';
+                self.escaped = !self.escaped && ch == '\\';
+                if closed {
+                    (TextKind::Tag, State::Tag)
+                } else {
+                    (TextKind::Tag, State::TagValue)
+                }
+            }
+            State::BraceComment if ch == '}' => (TextKind::Comment, State::Movetext),
+            State::LineComment if ch == '\n' => (TextKind::Comment, State::Movetext),
+            state => (TextKind::Comment, state),
+        };
+        self.state = state;
+        kind
+    }
+
+    // whether the last character, a "[", ended a comment or tag value that was never closed
+    pub(crate) fn recovered(&self) -> bool {
+        self.recovered
+    }
+}
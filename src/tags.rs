@@ -0,0 +1,180 @@
+// tag pairs of a game, kept in the order they were written
+
+use crate::GameResult;
+use std::ops::Index;
+
+// the tags every game should have, in the order the export format lists them
+pub const SEVEN_TAG_ROSTER: [&str; 7] =
+    ["Event", "Site", "Date", "Round", "White", "Black", "Result"];
+
+// tags in source order. a tag repeated in the source is kept every time it appears,
+// lookups see its first value
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Tags {
+    pairs: Vec<(String, String)>,
+}
+
+impl Tags {
+    pub fn new() -> Tags {
+        Tags::default()
+    }
+
+    // first value of the tag
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.pairs
+            .iter()
+            .find(|(tag, _)| tag == key)
+            .map(|(_, value)| value.as_str())
+    }
+
+    // every value of the tag, in source order
+    pub fn get_all<'a>(&'a self, key: &'a str) -> impl Iterator<Item = &'a str> {
+        self.pairs
+            .iter()
+            .filter(move |(tag, _)| tag == key)
+            .map(|(_, value)| value.as_str())
+    }
+
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.get(key).is_some()
+    }
+
+    // adds a tag at the end, even when the game already has it
+    pub fn push(&mut self, key: impl Into<String>, value: impl Into<String>) {
+        self.pairs.push((key.into(), value.into()));
+    }
+
+    // sets the tag's value in place, dropping any repeats, or adds the tag at the end
+    pub fn set(&mut self, key: &str, value: impl Into<String>) {
+        let value = value.into();
+        match self.pairs.iter().position(|(tag, _)| tag == key) {
+            Some(index) => {
+                self.pairs[index].1 = value;
+                let mut seen = 0;
+                self.pairs.retain(|(tag, _)| {
+                    seen += usize::from(tag == key);
+                    tag != key || seen == 1
+                });
+            }
+            None => self.push(key, value),
+        }
+    }
+
+    // removes every occurrence of the tag, returning its first value
+    pub fn remove(&mut self, key: &str) -> Option<String> {
+        let index = self.pairs.iter().position(|(tag, _)| tag == key)?;
+        let (_, value) = self.pairs.remove(index);
+        self.pairs.retain(|(tag, _)| tag != key);
+        Some(value)
+    }
+
+    pub fn len(&self) -> usize {
+        self.pairs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pairs.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.pairs
+            .iter()
+            .map(|(key, value)| (key.as_str(), value.as_str()))
+    }
+
+    // tags appearing more than once, in the order they are first repeated
+    pub fn duplicates(&self) -> Vec<&str> {
+        let mut duplicates: Vec<&str> = Vec::new();
+        for (index, (key, _)) in self.pairs.iter().enumerate() {
+            let repeated = self.pairs[..index].iter().any(|(tag, _)| tag == key);
+            if repeated && !duplicates.contains(&key.as_str()) {
+                duplicates.push(key);
+            }
+        }
+        duplicates
+    }
+
+    // the Seven Tag Roster
+    pub fn event(&self) -> Option<&str> {
+        self.get("Event")
+    }
+
+    pub fn site(&self) -> Option<&str> {
+        self.get("Site")
+    }
+
+    pub fn date(&self) -> Option<&str> {
+        self.get("Date")
+    }
+
+    pub fn round(&self) -> Option<&str> {
+        self.get("Round")
+    }
+
+    pub fn white(&self) -> Option<&str> {
+        self.get("White")
+    }
+
+    pub fn black(&self) -> Option<&str> {
+        self.get("Black")
+    }
+
+    // the Result tag, none when missing or not a valid result
+    pub fn result(&self) -> Option<GameResult> {
+        self.get("Result")?.parse().ok()
+    }
+}
+
+// tag value as written in PGN, with quotes and backslashes escaped
+pub(crate) fn escape_tag_value(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+// tag value from its PGN spelling: \" and \\ stand for a quote and a backslash,
+// any other backslash is kept as it is
+pub(crate) fn unescape_tag_value(text: &str) -> String {
+    let mut value = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(ch) = chars.next() {
+        match (ch, chars.peek()) {
+            ('\\', Some(&escaped)) if escaped == '"' || escaped == '\\' => {
+                value.push(escaped);
+                chars.next();
+            }
+            _ => value.push(ch),
+        }
+    }
+    value
+}
+
+// value of a tag the game is known to have, panicking otherwise (like a map)
+impl Index<&str> for Tags {
+    type Output = String;
+
+    fn index(&self, key: &str) -> &String {
+        self.pairs
+            .iter()
+            .find(|(tag, _)| tag == key)
+            .map(|(_, value)| value)
+            .unwrap_or_else(|| panic!("no tag named '{}'", key))
+    }
+}
+
+impl<'a> IntoIterator for &'a Tags {
+    type Item = (&'a str, &'a str);
+    type IntoIter = Box<dyn Iterator<Item = (&'a str, &'a str)> + 'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        Box::new(self.iter())
+    }
+}
+
+impl<K: Into<String>, V: Into<String>> FromIterator<(K, V)> for Tags {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(pairs: I) -> Tags {
+        let mut tags = Tags::new();
+        for (key, value) in pairs {
+            tags.push(key, value);
+        }
+        tags
+    }
+}
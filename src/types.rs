@@ -0,0 +1,311 @@
+// basic chess values shared by parsed moves and the board: colors, pieces, files, ranks and squares
+
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Color {
+    White,
+    Black,
+}
+
+impl Color {
+    pub fn opponent(self) -> Color {
+        match self {
+            Color::White => Color::Black,
+            Color::Black => Color::White,
+        }
+    }
+
+    // direction pawns of this color move in
+    pub(crate) fn forward(self) -> i8 {
+        match self {
+            Color::White => 1,
+            Color::Black => -1,
+        }
+    }
+
+    // rank the pieces of this color start on (0-based)
+    pub(crate) fn back_rank(self) -> u8 {
+        match self {
+            Color::White => 0,
+            Color::Black => 7,
+        }
+    }
+}
+
+impl fmt::Display for Color {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Color::White => write!(f, "white"),
+            Color::Black => write!(f, "black"),
+        }
+    }
+}
+
+// "white" or "black", or the "w"/"b" used by FEN
+impl FromStr for Color {
+    type Err = String;
+
+    fn from_str(color: &str) -> Result<Self, Self::Err> {
+        match color {
+            "white" | "w" => Ok(Color::White),
+            "black" | "b" => Ok(Color::Black),
+            _ => Err(format!("invalid color '{}'", color)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CastlingSide {
+    Kingside,  // "O-O"
+    Queenside, // "O-O-O"
+}
+
+impl fmt::Display for CastlingSide {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CastlingSide::Kingside => write!(f, "O-O"),
+            CastlingSide::Queenside => write!(f, "O-O-O"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Piece {
+    #[default]
+    Pawn,
+    Knight,
+    Bishop,
+    Rook,
+    Queen,
+    King,
+}
+
+impl Piece {
+    // uppercase letter of the piece, 'P' for pawns (which SAN leaves out)
+    pub fn letter(self) -> char {
+        match self {
+            Piece::Pawn => 'P',
+            Piece::Knight => 'N',
+            Piece::Bishop => 'B',
+            Piece::Rook => 'R',
+            Piece::Queen => 'Q',
+            Piece::King => 'K',
+        }
+    }
+
+    pub fn from_letter(letter: char) -> Option<Piece> {
+        match letter {
+            'P' => Some(Piece::Pawn),
+            'N' => Some(Piece::Knight),
+            'B' => Some(Piece::Bishop),
+            'R' => Some(Piece::Rook),
+            'Q' => Some(Piece::Queen),
+            'K' => Some(Piece::King),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Piece {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.letter())
+    }
+}
+
+impl FromStr for Piece {
+    type Err = String;
+
+    fn from_str(piece: &str) -> Result<Self, Self::Err> {
+        let mut chars = piece.chars();
+        match (chars.next().and_then(Piece::from_letter), chars.next()) {
+            (Some(piece), None) => Ok(piece),
+            _ => Err(format!("invalid piece '{}'", piece)),
+        }
+    }
+}
+
+// board column, 0-based ('a' = 0)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct File(pub(crate) u8);
+
+impl File {
+    pub fn new(index: u8) -> Option<File> {
+        (index < 8).then_some(File(index))
+    }
+
+    pub fn from_char(letter: char) -> Option<File> {
+        ('a'..='h')
+            .contains(&letter)
+            .then(|| File(letter as u8 - b'a'))
+    }
+
+    pub fn index(self) -> u8 {
+        self.0
+    }
+
+    pub fn to_char(self) -> char {
+        (b'a' + self.0) as char
+    }
+}
+
+impl fmt::Display for File {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.to_char())
+    }
+}
+
+impl FromStr for File {
+    type Err = String;
+
+    fn from_str(file: &str) -> Result<Self, Self::Err> {
+        let mut chars = file.chars();
+        match (chars.next().and_then(File::from_char), chars.next()) {
+            (Some(file), None) => Ok(file),
+            _ => Err(format!("invalid file '{}'", file)),
+        }
+    }
+}
+
+// board row, 0-based ('1' = 0)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Rank(pub(crate) u8);
+
+impl Rank {
+    pub fn new(index: u8) -> Option<Rank> {
+        (index < 8).then_some(Rank(index))
+    }
+
+    pub fn from_char(digit: char) -> Option<Rank> {
+        ('1'..='8')
+            .contains(&digit)
+            .then(|| Rank(digit as u8 - b'1'))
+    }
+
+    pub fn index(self) -> u8 {
+        self.0
+    }
+
+    pub fn to_char(self) -> char {
+        (b'1' + self.0) as char
+    }
+}
+
+impl fmt::Display for Rank {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.to_char())
+    }
+}
+
+impl FromStr for Rank {
+    type Err = String;
+
+    fn from_str(rank: &str) -> Result<Self, Self::Err> {
+        let mut chars = rank.chars();
+        match (chars.next().and_then(Rank::from_char), chars.next()) {
+            (Some(rank), None) => Ok(rank),
+            _ => Err(format!("invalid rank '{}'", rank)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Square {
+    pub file: File,
+    pub rank: Rank,
+}
+
+impl Square {
+    pub fn new(file: File, rank: Rank) -> Square {
+        Square { file, rank }
+    }
+
+    // square from 0-based file and rank indices
+    pub fn from_indices(file: u8, rank: u8) -> Option<Square> {
+        Some(Square::new(File::new(file)?, Rank::new(rank)?))
+    }
+
+    // every square of the board, rank by rank from a1
+    pub fn all() -> impl Iterator<Item = Square> {
+        (0..8).flat_map(|rank| (0..8).map(move |file| Square::new(File(file), Rank(rank))))
+    }
+
+    pub(crate) fn offset(self, file_step: i8, rank_step: i8) -> Option<Square> {
+        let file = self.file.0 as i8 + file_step;
+        let rank = self.rank.0 as i8 + rank_step;
+        if (0..8).contains(&file) && (0..8).contains(&rank) {
+            Square::from_indices(file as u8, rank as u8)
+        } else {
+            None
+        }
+    }
+}
+
+impl fmt::Display for Square {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", self.file, self.rank)
+    }
+}
+
+// square name, e.g. "e4"
+impl FromStr for Square {
+    type Err = String;
+
+    fn from_str(square: &str) -> Result<Self, Self::Err> {
+        let mut chars = square.chars();
+        match (
+            chars.next().and_then(File::from_char),
+            chars.next().and_then(Rank::from_char),
+            chars.next(),
+        ) {
+            (Some(file), Some(rank), None) => Ok(Square::new(file, rank)),
+            _ => Err(format!("invalid square '{}'", square)),
+        }
+    }
+}
+
+// origin of a move as far as SAN spells it out, to tell apart pieces that could reach the same square
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Disambiguation {
+    File(File),     // "Nbd2", and the file a pawn captures from ("exd5")
+    Rank(Rank),     // "R1e2"
+    Square(Square), // "Qh4e1"
+}
+
+impl Disambiguation {
+    // whether a piece moving from `from` fits the disambiguation
+    pub fn matches(self, from: Square) -> bool {
+        match self {
+            Disambiguation::File(file) => from.file == file,
+            Disambiguation::Rank(rank) => from.rank == rank,
+            Disambiguation::Square(square) => from == square,
+        }
+    }
+}
+
+impl fmt::Display for Disambiguation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Disambiguation::File(file) => write!(f, "{}", file),
+            Disambiguation::Rank(rank) => write!(f, "{}", rank),
+            Disambiguation::Square(square) => write!(f, "{}", square),
+        }
+    }
+}
+
+impl FromStr for Disambiguation {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        if let Ok(square) = text.parse() {
+            return Ok(Disambiguation::Square(square));
+        }
+        if let Ok(file) = text.parse() {
+            return Ok(Disambiguation::File(file));
+        }
+        text.parse()
+            .map(Disambiguation::Rank)
+            .map_err(|_| format!("invalid disambiguation '{}'", text))
+    }
+}
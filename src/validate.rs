@@ -0,0 +1,240 @@
+// consistency checks on parsed games that the grammar can't express
+
+use crate::types::Color;
+use crate::{replay_positions, ChessGame, ChessMove, GameResult, ReplayError};
+use std::fmt;
+
+// disagreement about how a game ended
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ResultIssue {
+    MissingTag,         // no Result tag
+    InvalidTag(String), // Result tag that isn't one of the four results
+    // the Result tag and the result closing the movetext differ
+    Mismatch {
+        tag: GameResult,
+        movetext: GameResult,
+    },
+    // the main line ends in checkmate, but a different result is declared
+    ContradictsCheckmate {
+        winner: Color,
+        declared: GameResult,
+    },
+}
+
+impl fmt::Display for ResultIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ResultIssue::MissingTag => write!(f, "missing Result tag"),
+            ResultIssue::InvalidTag(value) => write!(f, "invalid Result tag '{}'", value),
+            ResultIssue::Mismatch { tag, movetext } => write!(
+                f,
+                "Result tag '{}' doesn't match the movetext result '{}'",
+                tag, movetext
+            ),
+            ResultIssue::ContradictsCheckmate { winner, declared } => write!(
+                f,
+                "result '{}' contradicts the checkmate by {}",
+                declared, winner
+            ),
+        }
+    }
+}
+
+impl ChessGame {
+    // checks the Result tag against the movetext result and, when the main line can be
+    // replayed and ends in checkmate, both against the board
+    pub fn check_result(&self) -> Vec<ResultIssue> {
+        let mut issues = Vec::new();
+
+        let tag = match self.metadata.get("Result") {
+            None => {
+                issues.push(ResultIssue::MissingTag);
+                None
+            }
+            Some(value) => match value.parse::<GameResult>() {
+                Ok(tag) => Some(tag),
+                Err(_) => {
+                    issues.push(ResultIssue::InvalidTag(value.to_string()));
+                    None
+                }
+            },
+        };
+        if let Some(tag) = tag.filter(|tag| *tag != self.result) {
+            issues.push(ResultIssue::Mismatch {
+                tag,
+                movetext: self.result,
+            });
+        }
+
+        // games that can't be replayed are only checked as text
+        let final_position = replay_positions(self)
+            .ok()
+            .and_then(|positions| positions.into_iter().last());
+        if let Some(board) = final_position.filter(|board| board.is_checkmate()) {
+            let winner = board.side_to_move.opponent();
+            let expected = match winner {
+                Color::White => GameResult::WhiteWins,
+                Color::Black => GameResult::BlackWins,
+            };
+            // an unknown result doesn't claim anything
+            let mut declared: Vec<GameResult> = tag.into_iter().collect();
+            if !declared.contains(&self.result) {
+                declared.push(self.result);
+            }
+            for declared in declared {
+                if declared != expected && declared != GameResult::Unknown {
+                    issues.push(ResultIssue::ContradictsCheckmate { winner, declared });
+                }
+            }
+        }
+
+        issues
+    }
+}
+
+// check or mate suffix of a move
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CheckMark {
+    Check, // "+"
+    Mate,  // "#"
+}
+
+impl fmt::Display for CheckMark {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CheckMark::Check => write!(f, "+"),
+            CheckMark::Mate => write!(f, "#"),
+        }
+    }
+}
+
+// move whose check or mate suffix doesn't match the position it leads to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CheckMarkIssue {
+    pub turn_number: usize,
+    pub color: Color,
+    pub move_text: String,
+    pub written: Option<CheckMark>, // none when the move has no suffix
+    pub actual: Option<CheckMark>,  // none when the move gives no check
+    pub line: usize,                // position of the move in the source, 0 when unknown
+    pub column: usize,
+}
+
+impl fmt::Display for CheckMarkIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.written, self.actual) {
+            (None, Some(actual)) => write!(f, "missing '{}'", actual)?,
+            (Some(written), None) => write!(f, "spurious '{}'", written)?,
+            (Some(written), Some(actual)) => write!(f, "'{}' instead of '{}'", written, actual)?,
+            (None, None) => write!(f, "no check mark problem")?,
+        }
+        write!(
+            f,
+            " on '{}' in turn {} ({}) at line {}, column {}",
+            self.move_text, self.turn_number, self.color, self.line, self.column
+        )
+    }
+}
+
+impl ChessMove {
+    // the check or mate suffix as written
+    pub fn check_mark(&self) -> Option<CheckMark> {
+        if self.mate {
+            Some(CheckMark::Mate)
+        } else if self.check {
+            Some(CheckMark::Check)
+        } else {
+            None
+        }
+    }
+
+    // replaces the check or mate suffix, keeping the move quality after it
+    pub fn set_check_mark(&mut self, mark: Option<CheckMark>) {
+        // a move edited by hand may not end with its annotation
+        let base_length = self
+            .full_str
+            .strip_suffix(self.annotation.as_str())
+            .map_or(self.full_str.len(), str::len);
+        self.full_str.truncate(base_length);
+        self.annotation = mark.map(|mark| mark.to_string()).unwrap_or_default() + &self.quality;
+        self.full_str.push_str(&self.annotation);
+        self.check = mark == Some(CheckMark::Check);
+        self.mate = mark == Some(CheckMark::Mate);
+    }
+}
+
+impl ChessGame {
+    // replays the main line and reports every move whose check or mate suffix is missing,
+    // spurious or wrong
+    pub fn verify_check_marks(&self) -> Result<Vec<CheckMarkIssue>, ReplayError> {
+        let mut actual_marks = actual_check_marks(self)?.into_iter();
+        let mut issues = Vec::new();
+
+        for turn in &self.turns {
+            let moves = [
+                (Color::White, &turn.white_move),
+                (Color::Black, &turn.black_move),
+            ];
+            for (color, mv) in moves {
+                let Some(mv) = mv.as_ref().filter(|mv| mv.is_played()) else {
+                    continue;
+                };
+                let actual = actual_marks.next().flatten();
+                if mv.check_mark() != actual {
+                    issues.push(CheckMarkIssue {
+                        turn_number: turn.turn_number,
+                        color,
+                        move_text: mv.full_str.clone(),
+                        written: mv.check_mark(),
+                        actual,
+                        line: mv.line,
+                        column: mv.column,
+                    });
+                }
+            }
+        }
+
+        Ok(issues)
+    }
+
+    // rewrites the check and mate suffixes of the main line to match the board,
+    // returning how many moves changed
+    pub fn normalize_check_marks(&mut self) -> Result<usize, ReplayError> {
+        let mut actual_marks = actual_check_marks(self)?.into_iter();
+        let mut changed = 0;
+
+        for turn in &mut self.turns {
+            for mv in [&mut turn.white_move, &mut turn.black_move]
+                .into_iter()
+                .flatten()
+                .filter(|mv| mv.is_played())
+            {
+                let actual = actual_marks.next().flatten();
+                if mv.check_mark() != actual {
+                    mv.set_check_mark(actual);
+                    changed += 1;
+                }
+            }
+        }
+
+        Ok(changed)
+    }
+}
+
+// the suffix every move of the main line should have, from the position it leads to
+fn actual_check_marks(game: &ChessGame) -> Result<Vec<Option<CheckMark>>, ReplayError> {
+    let positions = replay_positions(game)?;
+    Ok(positions
+        .iter()
+        .skip(1)
+        .map(|board| {
+            if board.is_checkmate() {
+                Some(CheckMark::Mate)
+            } else if board.is_check() {
+                Some(CheckMark::Check)
+            } else {
+                None
+            }
+        })
+        .collect())
+}
@@ -0,0 +1,162 @@
+// PGN export: tags in Seven Tag Roster order, then the movetext wrapped to fit in 80 columns
+
+use crate::tags::escape_tag_value;
+use crate::{ChessGame, ChessMove, ChessTurn, PieceLetters, SEVEN_TAG_ROSTER};
+
+// longest movetext line written, so lines fit in 80 columns
+const MAX_LINE_LENGTH: usize = 79;
+
+impl ChessGame {
+    // the game in PGN export format. parsing the text back gives the same tags, moves,
+    // comments, NAGs, variations and result
+    pub fn to_pgn(&self) -> String {
+        self.to_pgn_with_letters(&PieceLetters::ENGLISH)
+    }
+
+    // the game in PGN export format, with the pieces named by `letters`, e.g. "Sf3" in German
+    pub fn to_pgn_with_letters(&self, letters: &PieceLetters) -> String {
+        let mut pgn = String::new();
+
+        // the Seven Tag Roster first, then the other tags in source order
+        let roster_tags = SEVEN_TAG_ROSTER
+            .iter()
+            .flat_map(|key| self.metadata.get_all(key).map(move |value| (*key, value)));
+        let other_tags = self
+            .metadata
+            .iter()
+            .filter(|(key, _)| !SEVEN_TAG_ROSTER.contains(key));
+        for (key, value) in roster_tags.chain(other_tags) {
+            pgn.push_str(&format!("[{} \"{}\"]\n", key, escape_tag_value(value)));
+        }
+        if !pgn.is_empty() {
+            pgn.push('\n');
+        }
+
+        let mut tokens = Vec::new();
+        write_turns(&mut tokens, &self.turns, letters);
+        tokens.extend(self.comments.iter().map(|comment| comment_token(comment)));
+        tokens.push(Token::text(self.result.to_string()));
+        pgn.push_str(&wrap_tokens(tokens));
+        pgn.push('\n');
+        pgn
+    }
+}
+
+// a piece of movetext, kept on one line
+struct Token {
+    text: String,
+    line_comment: bool, // ends the line it is on
+}
+
+impl Token {
+    fn text(text: String) -> Token {
+        Token {
+            text,
+            line_comment: false,
+        }
+    }
+}
+
+fn write_turns(tokens: &mut Vec<Token>, turns: &[ChessTurn], letters: &PieceLetters) {
+    for turn in turns {
+        let mut black_needs_number = true;
+        if let Some(white_move) = turn.white_move.as_ref().filter(|mv| mv.is_played()) {
+            tokens.push(Token::text(format!("{}.", turn.turn_number)));
+            write_move(tokens, white_move, letters);
+            // black's move is renumbered after a comment or variation
+            black_needs_number =
+                !white_move.comments.is_empty() || !white_move.variations.is_empty();
+        }
+        if let Some(black_move) = turn.black_move.as_ref().filter(|mv| mv.is_played()) {
+            if black_needs_number {
+                tokens.push(Token::text(format!("{}...", turn.turn_number)));
+            }
+            write_move(tokens, black_move, letters);
+        }
+    }
+}
+
+// a move after its move number: comments leading up to it, the move, its NAGs, comments and variations
+fn write_move(tokens: &mut Vec<Token>, mv: &ChessMove, letters: &PieceLetters) {
+    for comment in &mv.pre_comments {
+        tokens.push(comment_token(comment));
+    }
+    tokens.push(Token::text(letters.translate_move(&mv.full_str)));
+    if mv.en_passant_suffix {
+        tokens.push(Token::text("e.p.".to_string()));
+    }
+    for nag in &mv.nags {
+        tokens.push(Token::text(format!("${}", nag)));
+    }
+    for comment in &mv.comments {
+        tokens.push(comment_token(comment));
+    }
+    for variation in &mv.variations {
+        let mut inner = Vec::new();
+        write_turns(&mut inner, &variation.turns, letters);
+        inner.extend(
+            variation
+                .comments
+                .iter()
+                .map(|comment| comment_token(comment)),
+        );
+        tokens.push(Token::text("(".to_string()));
+        tokens.extend(inner);
+        tokens.push(Token::text(")".to_string()));
+    }
+}
+
+// comments go in braces, unless they contain a closing brace (only possible in a ';' comment)
+fn comment_token(comment: &str) -> Token {
+    if comment.contains('}') {
+        Token {
+            text: format!(";{}", comment.replace('\n', " ")),
+            line_comment: true,
+        }
+    } else {
+        Token::text(format!("{{{}}}", comment))
+    }
+}
+
+// joins the tokens with single spaces, starting a new line before a token that wouldn't fit.
+// parentheses stick to the tokens they enclose, and a comment is never broken, so reading it
+// back gives the same text (one longer than a line is written past the line's end)
+fn wrap_tokens(tokens: Vec<Token>) -> String {
+    let mut words: Vec<Token> = Vec::new();
+    let mut open = String::new();
+    for token in tokens {
+        match token.text.as_str() {
+            "(" => open.push('('),
+            ")" if open.is_empty() => match words.last_mut() {
+                Some(last) if !last.line_comment => last.text.push(')'),
+                _ => words.push(token),
+            },
+            // an empty variation
+            ")" => words.push(Token::text(std::mem::take(&mut open) + ")")),
+            _ => words.push(Token {
+                text: std::mem::take(&mut open) + &token.text,
+                ..token
+            }),
+        }
+    }
+
+    let mut text = String::new();
+    let mut line_length = 0;
+    let mut line_ended = false;
+    for word in words {
+        let length = word.text.chars().count();
+        if line_length > 0 {
+            if line_ended || line_length + 1 + length > MAX_LINE_LENGTH {
+                text.push('\n');
+                line_length = 0;
+            } else {
+                text.push(' ');
+                line_length += 1;
+            }
+        }
+        text.push_str(&word.text);
+        line_length += length;
+        line_ended = word.line_comment;
+    }
+    text
+}
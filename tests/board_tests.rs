@@ -0,0 +1,229 @@
+use chess_parser::{
+    parse_pgn, parse_pgn_with_options, replay_game, replay_positions, Board, Color, ParseOptions,
+    Piece, ReplayErrorKind, Square,
+};
+
+fn square(name: &str) -> Square {
+    name.parse().unwrap()
+}
+
+#[test]
+fn test_replay_example_games() {
+    for file in [
+        "examples/fisher_spassky.chess",
+        "examples/topalov_kasparov.chess",
+    ] {
+        let pgn_text = std::fs::read_to_string(file).unwrap();
+        let game = parse_pgn(&pgn_text).unwrap();
+        let played = replay_game(&game).unwrap();
+        assert_eq!(played.len(), game.mainline().len());
+    }
+}
+
+#[test]
+fn test_replay_resolves_squares() {
+    let pgn_text = "1.e4 e5 2.Nf3 Nc6 3.Bb5 Nf6 4.O-O Nxe4 5.Re1 Nd6 6.Nc3 Nxb5 7.Nxb5 Be7 1-0";
+    let game = parse_pgn(pgn_text).unwrap();
+    let played = replay_game(&game).unwrap();
+
+    assert_eq!(played[0].from, square("e2"));
+    assert_eq!(played[0].to, square("e4"));
+    assert_eq!(played[0].piece, Piece::Pawn);
+
+    let nxe4 = &played[7];
+    assert_eq!(nxe4.from, square("f6"));
+    assert_eq!(nxe4.captured, Some(Piece::Pawn));
+    assert_eq!(nxe4.color, Color::Black);
+
+    let castle = &played[6];
+    assert_eq!(castle.color, Color::White);
+    assert!(castle.castling);
+    assert_eq!(castle.from, square("e1"));
+    assert_eq!(castle.to, square("g1"));
+
+    let nxb5 = &played[12];
+    assert_eq!(nxb5.from, square("c3"));
+    assert_eq!(nxb5.captured, Some(Piece::Knight));
+}
+
+#[test]
+fn test_replay_disambiguation() {
+    let pgn_text = "1.Nf3 Nf6 2.Nc3 Nc6 3.Nd4 Nd5 4.Ncb5 Nf4 5.Nf5 Ne6 6.Nfd6+ 1-0";
+    let game = parse_pgn(pgn_text).unwrap();
+    let played = replay_game(&game).unwrap();
+    assert_eq!(played[6].from, square("c3"));
+    assert_eq!(played[10].from, square("f5"));
+
+    let pgn_text = "1.a4 h5 2.h4 Rh6 3.Rh3 Ra6 4.Rha3 Rb6 5.R1a2 1-0";
+    let game = parse_pgn(pgn_text).unwrap();
+    let played = replay_game(&game).unwrap();
+    assert_eq!(played[6].from, square("h3"));
+    assert_eq!(played[8].from, square("a1"));
+}
+
+#[test]
+fn test_replay_en_passant_and_promotion() {
+    let pgn_text = "1.e4 Nf6 2.e5 d5 3.exd6 Ng8 4.dxc7 Nf6 5.cxd8=Q+ Kxd8 1-0";
+    let game = parse_pgn(pgn_text).unwrap();
+    let played = replay_game(&game).unwrap();
+
+    assert!(played[4].en_passant);
+    assert_eq!(played[4].captured, Some(Piece::Pawn));
+    assert_eq!(played[8].promotion, Some(Piece::Queen));
+    assert_eq!(played[8].captured, Some(Piece::Queen));
+}
+
+#[test]
+fn test_en_passant_is_marked_on_moves() {
+    let pgn_text = "1.e4 Nf6 2.e5 d5 3.exd6 (3.Nc3 Nc6 4.exf6) 3...Ng8 4.d4 c5 5.dxc5 *";
+    let mut game = parse_pgn(pgn_text).unwrap();
+    // only replaying finds en passant captures
+    assert!(!game.turns[2].white_move.as_ref().unwrap().en_passant);
+    assert_eq!(game.mark_en_passant().unwrap(), 1);
+
+    let exd6 = game.turns[2].white_move.as_ref().unwrap();
+    assert!(exd6.en_passant);
+    assert!(
+        !exd6.variations[0].turns[1]
+            .white_move
+            .as_ref()
+            .unwrap()
+            .en_passant
+    );
+    assert!(!game.turns[4].white_move.as_ref().unwrap().en_passant);
+
+    // validated games come marked
+    let options = ParseOptions {
+        validate_moves: true,
+        ..ParseOptions::default()
+    };
+    let game = parse_pgn_with_options(pgn_text, &options).unwrap();
+    assert!(game.turns[2].white_move.as_ref().unwrap().en_passant);
+}
+
+#[test]
+fn test_replay_null_moves() {
+    let game = parse_pgn("1.e4 -- 2.d4 -- 3.Nf3 e5 *").unwrap();
+    let played = replay_game(&game).unwrap();
+    assert!(played[1].null);
+    assert_eq!(played[1].color, Color::Black);
+    assert_eq!(played[1].from, square("e8"));
+    assert_eq!(played[1].to, square("e8"));
+    assert_eq!(played[1].uci(), "0000");
+    assert_eq!(
+        game.uci_moves().unwrap(),
+        ["e2e4", "0000", "d2d4", "0000", "g1f3", "e7e5"]
+    );
+
+    // a null move only switches the side to move
+    let positions = replay_positions(&game).unwrap();
+    assert_eq!(
+        positions[2].to_fen(),
+        "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR w KQkq - 1 2"
+    );
+    assert_eq!(Board::default().san(&played[1]), "--");
+
+    // passing isn't allowed in check
+    let game = parse_pgn("1.e4 f5 2.Qh5+ -- *").unwrap();
+    let error = replay_game(&game).unwrap_err();
+    assert_eq!(error.kind, ReplayErrorKind::Illegal);
+    assert_eq!(error.move_text, "--");
+}
+
+#[test]
+fn test_replay_illegal_move() {
+    let game = parse_pgn("1.e4 e5 2.Ke3 Nc6 3.Nf3 Ke7 1-0").unwrap();
+    let error = replay_game(&game).unwrap_err();
+    assert_eq!(error.turn_number, 2);
+    assert_eq!(error.color, Color::White);
+    assert_eq!(error.move_text, "Ke3");
+    assert_eq!(error.kind, ReplayErrorKind::Illegal);
+    assert_eq!(error.to_string(), "illegal move 'Ke3' in turn 2 (white)");
+
+    // moving into check
+    let game = parse_pgn("1.e4 e5 2.Qh5 Ke7 3.Qxe5+ Kf6 1-0").unwrap();
+    let error = replay_game(&game).unwrap_err();
+    assert_eq!(error.turn_number, 3);
+    assert_eq!(error.color, Color::Black);
+    assert_eq!(error.move_text, "Kf6");
+
+    // pawns can't promote without saying what to
+    let game = parse_pgn("1.e4 d5 2.exd5 c6 3.dxc6 Nf6 4.cxb7 Nc6 5.bxa8 1-0").unwrap();
+    assert_eq!(
+        replay_game(&game).unwrap_err().kind,
+        ReplayErrorKind::Illegal
+    );
+}
+
+#[test]
+fn test_replay_capture_marks() {
+    // "x" onto an empty square
+    let game = parse_pgn("1.Nxf3 e5 1-0").unwrap();
+    let error = replay_game(&game).unwrap_err();
+    assert_eq!(error.kind, ReplayErrorKind::Illegal);
+    assert_eq!(error.move_text, "Nxf3");
+    let options = ParseOptions {
+        validate_moves: true,
+        ..ParseOptions::default()
+    };
+    assert!(parse_pgn_with_options("1.Nxf3 e5 1-0", &options).is_err());
+
+    // a capture without "x"
+    let game = parse_pgn("1.e4 d5 2.Nc3 Nf6 3.Nd5 1-0").unwrap();
+    assert_eq!(replay_game(&game).unwrap_err().move_text, "Nd5");
+
+    let game = parse_pgn("1.e4 d5 2.Nc3 Nf6 3.Nxd5 1-0").unwrap();
+    assert_eq!(replay_game(&game).unwrap()[4].captured, Some(Piece::Pawn));
+}
+
+#[test]
+fn test_replay_ambiguous_move() {
+    let game = parse_pgn("1.Nf3 Nf6 2.Nc3 Nc6 3.Nd4 Nd5 4.Nb5 1-0").unwrap();
+    let error = replay_game(&game).unwrap_err();
+    assert_eq!(error.turn_number, 4);
+    assert_eq!(error.kind, ReplayErrorKind::Ambiguous);
+}
+
+#[test]
+fn test_replay_castling_rules() {
+    let game = parse_pgn("1.e4 e5 2.Nf3 Nc6 3.Bc4 Bc5 4.O-O 1-0").unwrap();
+    assert!(replay_game(&game).is_ok());
+
+    // castling through an attacked square
+    let game = parse_pgn("1.e4 b6 2.Nf3 Ba6 3.Bc4 Bxc4 4.O-O 1-0").unwrap();
+    let error = replay_game(&game).unwrap_err();
+    assert_eq!(error.turn_number, 4);
+    assert_eq!(error.kind, ReplayErrorKind::Illegal);
+
+    // castling after the king has moved
+    let game = parse_pgn("1.e4 e5 2.Nf3 Nc6 3.Bc4 Bc5 4.Ke2 d6 5.Ke1 d5 6.O-O 1-0").unwrap();
+    assert_eq!(replay_game(&game).unwrap_err().turn_number, 6);
+}
+
+#[test]
+fn test_replay_out_of_turn() {
+    let game = parse_pgn("1... e5 2.Nf3 1-0").unwrap();
+    let error = replay_game(&game).unwrap_err();
+    assert_eq!(error.kind, ReplayErrorKind::OutOfTurn);
+    assert_eq!(error.color, Color::Black);
+}
+
+#[test]
+fn test_board_state() {
+    let mut board = Board::default();
+    assert_eq!(board.legal_moves().len(), 20);
+    assert_eq!(
+        board.piece_at(square("e1")),
+        Some((Color::White, Piece::King))
+    );
+    assert!(!board.is_check());
+
+    let game = parse_pgn("1.f3 e5 2.g4 Qh4# 0-1").unwrap();
+    for mv in game.mainline() {
+        board.play(mv).unwrap();
+    }
+    assert!(board.is_check());
+    assert!(board.is_checkmate());
+    assert_eq!(board.side_to_move, Color::White);
+    assert_eq!(board.fullmove_number, 3);
+}
@@ -0,0 +1,153 @@
+use chess_parser::{
+    parse_pgn, parse_pgn_database, parse_pgn_lenient, parse_pgn_with_options, Color, ParseOptions,
+    PgnError, PgnReader, ReplayErrorKind,
+};
+use std::io::Cursor;
+
+#[test]
+fn test_syntax_error_position() {
+    let pgn_text = "[Event \"Test\"]\n\n1.e4 e5\n2.Nf3  Uc6 3.Bb5 1-0";
+    let error = parse_pgn(pgn_text).unwrap_err();
+    assert_eq!(error.position(), Some((4, 8)));
+    assert_eq!(error.token(), Some("Uc6"));
+    assert!(matches!(error, PgnError::Syntax { .. }));
+    assert!(error
+        .to_string()
+        .starts_with("syntax error at line 4, column 8: unexpected 'Uc6', expected"));
+}
+
+#[test]
+fn test_unexpected_end() {
+    let error = parse_pgn("1.e4 e5\n2.Nf3 Nc6\n").unwrap_err();
+    assert!(matches!(error, PgnError::UnexpectedEnd { line: 3, .. }));
+    assert_eq!(error.token(), None);
+
+    let error = parse_pgn("1.e4 (1.d4 d5").unwrap_err();
+    assert!(matches!(error, PgnError::UnexpectedEnd { .. }));
+}
+
+#[test]
+fn test_text_after_the_result() {
+    let error = parse_pgn("1.e4 e5 1-0 garbage here").unwrap_err();
+    assert_eq!(error.position(), Some((1, 13)));
+    assert!(matches!(
+        error,
+        PgnError::Syntax { ref expected, .. } if expected == "end of input"
+    ));
+
+    // a second game isn't silently dropped
+    let error = parse_pgn("1.e4 e5 1-0\n\n1.d4 d5 0-1").unwrap_err();
+    assert_eq!(error.position(), Some((3, 1)));
+
+    let (_, diagnostics) = parse_pgn_lenient("1.e4 e5 1-0 garbage here");
+    assert!(!diagnostics.is_empty());
+    assert_eq!(diagnostics[0].snippet, "garbage");
+}
+
+#[test]
+fn test_bad_tag() {
+    let pgn_text = "[Event \"Test\"]\n[Site Somewhere]\n1.e4 e5 1-0";
+    let error = parse_pgn(pgn_text).unwrap_err();
+    match error {
+        PgnError::BadTag {
+            line,
+            column,
+            ref token,
+            ..
+        } => {
+            assert_eq!((line, column), (2, 1));
+            assert_eq!(token, "[Site Somewhere]");
+        }
+        _ => panic!("expected a tag error, got {:?}", error),
+    }
+}
+
+#[test]
+fn test_database_error_positions() {
+    let pgn_text =
+        "[Event \"Good\"]\n1.e4 e5 1-0\n\n[Event \"Bad\"]\n1.e4 e5\n2.Ue4 0-1 1.d4 Xd5 1-0";
+    let games = parse_pgn_database(pgn_text);
+    assert_eq!(games.len(), 3);
+
+    let error = games[1].as_ref().unwrap_err();
+    assert_eq!(error.position(), Some((6, 3)));
+    assert_eq!(error.token(), Some("Ue4"));
+
+    // the third game starts in the middle of line 6
+    let error = games[2].as_ref().unwrap_err();
+    assert_eq!(error.position(), Some((6, 16)));
+    assert_eq!(error.token(), Some("Xd5"));
+}
+
+#[test]
+fn test_reader_error_positions() {
+    let pgn_text =
+        "[Event \"Good\"]\n1.e4 e5 1-0\n\n[Event \"Bad\"]\n1.e4 e5\n2.Ue4 0-1 1.d4 Xd5 1-0";
+    let games: Vec<_> = PgnReader::new(Cursor::new(pgn_text)).collect();
+    assert_eq!(games.len(), 3);
+    assert_eq!(games[1].as_ref().unwrap_err().position(), Some((6, 3)));
+    assert_eq!(games[2].as_ref().unwrap_err().position(), Some((6, 16)));
+}
+
+#[test]
+fn test_move_positions() {
+    let pgn_text =
+        "[Event \"Test\"]\n1.e4 e5\n2.Nf3 (2.Bc4 Nf6) Nc6 1-0\n[Event \"Second\"]\n1.d4 d5 1-0";
+    let game = parse_pgn_database(pgn_text).remove(0).unwrap();
+    let mainline = game.mainline();
+    assert_eq!((mainline[0].line, mainline[0].column), (2, 3));
+    assert_eq!((mainline[3].line, mainline[3].column), (3, 19));
+
+    let variation = &mainline[2].variations[0];
+    let bc4 = variation.turns[0].white_move.as_ref().unwrap();
+    assert_eq!((bc4.line, bc4.column), (3, 10));
+
+    let second = parse_pgn_database(pgn_text).remove(1).unwrap();
+    assert_eq!(second.mainline()[1].line, 5);
+}
+
+#[test]
+fn test_validate_moves() {
+    let options = ParseOptions {
+        validate_moves: true,
+        ..Default::default()
+    };
+    assert!(parse_pgn_with_options("1.e4 e5 2.Nf3 Nc6 1-0", &options).is_ok());
+    // by default only the syntax is checked
+    assert!(parse_pgn("1.e4 e5 2.Ke3 1-0").is_ok());
+
+    let error = parse_pgn_with_options("1.e4 e5\n2.Ke3 Nc6 1-0", &options).unwrap_err();
+    match error {
+        PgnError::IllegalMove {
+            line,
+            column,
+            ref token,
+            turn_number,
+            color,
+            ref kind,
+        } => {
+            assert_eq!((line, column), (2, 3));
+            assert_eq!(token, "Ke3");
+            assert_eq!(turn_number, 2);
+            assert_eq!(color, Color::White);
+            assert_eq!(*kind, ReplayErrorKind::Illegal);
+        }
+        _ => panic!("expected an illegal move error, got {:?}", error),
+    }
+    assert_eq!(
+        error.to_string(),
+        "illegal move 'Ke3' in turn 2 (white) at line 2, column 3"
+    );
+
+    let pgn_text = "[Event \"Set up\"]\n[SetUp \"1\"]\n[FEN \"8/8/8 w - -\"]\n1.e4 *";
+    let error = parse_pgn_with_options(pgn_text, &options).unwrap_err();
+    assert!(matches!(
+        error,
+        PgnError::BadTag {
+            line: 3,
+            column: 1,
+            ..
+        }
+    ));
+    assert_eq!(error.token(), Some("[FEN \"8/8/8 w - -\"]"));
+}
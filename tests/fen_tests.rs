@@ -0,0 +1,156 @@
+use chess_parser::{
+    parse_pgn, replay_game, replay_positions, Board, Color, FenError, Piece, ReplayErrorKind,
+    STARTING_FEN,
+};
+
+#[test]
+fn test_fen_starting_position() {
+    assert_eq!(Board::default().to_fen(), STARTING_FEN);
+    assert_eq!(Board::from_fen(STARTING_FEN).unwrap(), Board::default());
+}
+
+#[test]
+fn test_fen_round_trip() {
+    let fens = [
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+        "rnbqkbnr/pp1ppppp/8/2p5/4P3/8/PPPP1PPP/RNBQKBNR w KQkq c6 0 2",
+        "4k3/8/8/8/8/8/8/4K2R b K - 12 40",
+    ];
+    for fen in fens {
+        assert_eq!(Board::from_fen(fen).unwrap().to_fen(), fen);
+    }
+}
+
+#[test]
+fn test_fen_fields() {
+    let board =
+        Board::from_fen("rnbqkbnr/pp1ppppp/8/2p5/4P3/8/PPPP1PPP/RNBQKBNR w Kq c6 3 2").unwrap();
+    assert_eq!(board.side_to_move, Color::White);
+    assert!(board.castling.white_kingside);
+    assert!(!board.castling.white_queenside);
+    assert!(!board.castling.black_kingside);
+    assert!(board.castling.black_queenside);
+    assert_eq!(board.en_passant, "c6".parse().ok());
+    assert_eq!(board.halfmove_clock, 3);
+    assert_eq!(board.fullmove_number, 2);
+    assert_eq!(
+        board.piece_at("c5".parse().unwrap()),
+        Some((Color::Black, Piece::Pawn))
+    );
+
+    // the move counters may be left out
+    let board = Board::from_fen("4k3/8/8/8/8/8/8/4K3 b - -").unwrap();
+    assert_eq!(board.side_to_move, Color::Black);
+    assert_eq!(board.halfmove_clock, 0);
+    assert_eq!(board.fullmove_number, 1);
+}
+
+#[test]
+fn test_fen_errors() {
+    assert_eq!(
+        Board::from_fen("8/8/8 w - -"),
+        Err(FenError::Placement("8/8/8".to_string()))
+    );
+    assert_eq!(
+        Board::from_fen("4k3/8/8/8/8/8/8/4K3"),
+        Err(FenError::FieldCount(1))
+    );
+    assert!(matches!(
+        Board::from_fen("4k3/8/8/8/8/8/8/4K2 w - - 0 1"),
+        Err(FenError::Placement(_))
+    ));
+    assert!(matches!(
+        Board::from_fen("4k3/8/8/8/8/8/8/4X3 w - - 0 1"),
+        Err(FenError::Placement(_))
+    ));
+    // rank overflowing the board through empty squares
+    assert!(matches!(
+        Board::from_fen("4k3/8/8/8/8/8/8/4K3888888888888888888888888888888 w - - 0 1"),
+        Err(FenError::Placement(_))
+    ));
+    // no white king
+    assert!(matches!(
+        Board::from_fen("4k3/8/8/8/8/8/8/8 w - - 0 1"),
+        Err(FenError::Placement(_))
+    ));
+    assert_eq!(
+        Board::from_fen("4k3/8/8/8/8/8/8/4K3 x - - 0 1"),
+        Err(FenError::SideToMove("x".to_string()))
+    );
+    assert_eq!(
+        Board::from_fen("4k3/8/8/8/8/8/8/4K3 w KX - 0 1"),
+        Err(FenError::Castling("KX".to_string()))
+    );
+    assert_eq!(
+        Board::from_fen("4k3/8/8/8/8/8/8/4K3 w - e3 0 1"),
+        Err(FenError::EnPassant("e3".to_string()))
+    );
+    assert_eq!(
+        Board::from_fen("4k3/8/8/8/8/8/8/4K3 w - - x 1"),
+        Err(FenError::MoveNumber("x".to_string()))
+    );
+    assert_eq!(
+        FenError::Castling("KX".to_string()).to_string(),
+        "invalid castling rights 'KX'"
+    );
+}
+
+#[test]
+fn test_fen_after_each_ply() {
+    let game = parse_pgn("1.e4 c5 2.Nf3 1-0").unwrap();
+    let positions = replay_positions(&game).unwrap();
+    assert_eq!(positions.len(), 4);
+    assert_eq!(positions[0].to_fen(), STARTING_FEN);
+    assert_eq!(
+        positions[1].to_fen(),
+        "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1"
+    );
+    assert_eq!(
+        positions[2].to_fen(),
+        "rnbqkbnr/pp1ppppp/8/2p5/4P3/8/PPPP1PPP/RNBQKBNR w KQkq c6 0 2"
+    );
+    assert_eq!(
+        positions[3].to_fen(),
+        "rnbqkbnr/pp1ppppp/8/2p5/4P3/5N2/PPPP1PPP/RNBQKB1R b KQkq - 1 2"
+    );
+}
+
+#[test]
+fn test_game_from_fen_tag() {
+    let pgn_text = r#"[SetUp "1"]
+[FEN "4k3/8/8/8/8/8/8/R3K3 b Q - 0 30"]
+
+30... Kd7 31.O-O-O+ Kc6 1-0"#;
+    let game = parse_pgn(pgn_text).unwrap();
+    let starting = game.starting_position().unwrap();
+    assert_eq!(starting.side_to_move, Color::Black);
+    assert_eq!(starting.fullmove_number, 30);
+
+    let played = replay_game(&game).unwrap();
+    assert_eq!(played.len(), 3);
+    assert!(played[1].castling);
+    assert_eq!(
+        replay_positions(&game).unwrap().last().unwrap().to_fen(),
+        "8/8/2k5/8/8/8/8/2KR4 w - - 3 32"
+    );
+
+    // the standard position is used when the game isn't set up
+    let game =
+        parse_pgn("[SetUp \"0\"]\n[FEN \"4k3/8/8/8/8/8/8/R3K3 b Q - 0 30\"]\n1.e4 *").unwrap();
+    assert_eq!(game.starting_position().unwrap(), Board::default());
+}
+
+#[test]
+fn test_game_with_invalid_fen_tag() {
+    let game = parse_pgn("[SetUp \"1\"]\n[FEN \"not a fen\"]\n1.e4 *").unwrap();
+    let error = replay_game(&game).unwrap_err();
+    assert_eq!(
+        error.kind,
+        ReplayErrorKind::InvalidFen(FenError::FieldCount(3))
+    );
+    assert_eq!(
+        error.to_string(),
+        "invalid FEN tag 'not a fen': expected 4 to 6 FEN fields, found 3"
+    );
+}
@@ -0,0 +1,121 @@
+use chess_parser::{
+    parse_pgn_database_lenient, parse_pgn_lenient, parse_pgn_lenient_with_options, DiagnosticKind,
+    GameResult, ParseOptions, PgnReader,
+};
+use std::io::Cursor;
+
+#[test]
+fn test_lenient_skips_bad_tokens() {
+    let pgn_text = "[Event \"Test\"]\n1.e4 e5 2.Nf3 Uc6 3.Bb5 a6 1-0";
+    let (game, diagnostics) = parse_pgn_lenient(pgn_text);
+    assert_eq!(game.metadata["Event"], "Test");
+    assert_eq!(game.result, GameResult::WhiteWins);
+    assert_eq!(game.turns.len(), 3);
+    assert!(game.turns[1].black_move.is_none());
+    assert_eq!(game.turns[2].white_move.as_ref().unwrap().full_str, "Bb5");
+
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].kind, DiagnosticKind::Syntax);
+    assert_eq!((diagnostics[0].line, diagnostics[0].column), (2, 15));
+    assert_eq!(diagnostics[0].snippet, "Uc6");
+}
+
+#[test]
+fn test_lenient_collects_every_problem() {
+    let pgn_text = "[Event \"Test\"]\n[Site Somewhere]\n1.e9 e5 2.Nf3 Nc6 3.Bb5 Zz6";
+    let (game, diagnostics) = parse_pgn_lenient(pgn_text);
+    assert_eq!(game.metadata.len(), 1);
+    assert_eq!(game.result, GameResult::Unknown);
+    assert_eq!(game.turns[1].black_move.as_ref().unwrap().full_str, "Nc6");
+
+    let kinds: Vec<DiagnosticKind> = diagnostics.iter().map(|d| d.kind).collect();
+    assert_eq!(
+        kinds,
+        [
+            DiagnosticKind::BadTag,
+            DiagnosticKind::Syntax,
+            DiagnosticKind::Syntax,
+            DiagnosticKind::UnexpectedEnd
+        ]
+    );
+    assert_eq!(diagnostics[0].snippet, "[Site Somewhere]");
+    assert_eq!(diagnostics[1].line, 3);
+}
+
+#[test]
+fn test_lenient_without_problems() {
+    let (game, diagnostics) = parse_pgn_lenient("1.e4 e5 1/2-1/2");
+    assert!(diagnostics.is_empty());
+    assert_eq!(game.result, GameResult::Draw);
+}
+
+#[test]
+fn test_lenient_reports_illegal_moves() {
+    let options = ParseOptions {
+        validate_moves: true,
+        ..ParseOptions::default()
+    };
+    let (game, diagnostics) = parse_pgn_lenient_with_options("1.e4 e5 2.Ke3 Nc6 1-0", &options);
+    assert_eq!(game.turns.len(), 2);
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].kind, DiagnosticKind::IllegalMove);
+    assert_eq!(diagnostics[0].snippet, "Ke3");
+}
+
+#[test]
+fn test_lenient_database() {
+    let pgn_text = "[Event \"One\"]\n1.e4 Xx5 1-0\n\n%%%% ####\n\n[Event \"Three\"]\n1.d4 d5 0-1\n";
+    let (games, diagnostics) = parse_pgn_database_lenient(pgn_text, &ParseOptions::default());
+    assert_eq!(games.len(), 2);
+    assert_eq!(games[1].metadata["Event"], "Three");
+
+    let skipped: Vec<_> = diagnostics
+        .iter()
+        .filter(|d| d.kind == DiagnosticKind::SkippedGame)
+        .collect();
+    assert_eq!(skipped.len(), 1);
+    assert_eq!(skipped[0].line, 4);
+    assert_eq!(skipped[0].snippet, "%%%% ####");
+
+    // the reader recovers the same way, with positions in the whole input
+    let mut reader = PgnReader::new(Cursor::new(pgn_text));
+    let (game, game_diagnostics) = reader.next_game_lenient().unwrap().unwrap();
+    assert_eq!(game.unwrap().turns.len(), 1);
+    assert_eq!(game_diagnostics[0].line, 2);
+    let (game, _) = reader.next_game_lenient().unwrap().unwrap();
+    assert!(game.is_none());
+    let (game, game_diagnostics) = reader.next_game_lenient().unwrap().unwrap();
+    assert!(game.is_some() && game_diagnostics.is_empty());
+    assert!(reader.next_game_lenient().is_none());
+}
+
+#[test]
+fn test_lenient_keeps_the_color_of_later_moves() {
+    let options = ParseOptions {
+        validate_moves: true,
+        ..ParseOptions::default()
+    };
+    let pgn_text = "1.e4 e5 2.Nf9 Nc6 3.Bb5 a6 1-0";
+    for (game, diagnostics) in [
+        parse_pgn_lenient(pgn_text),
+        parse_pgn_lenient_with_options(pgn_text, &options),
+    ] {
+        assert_eq!(game.turns.len(), 3);
+        // white's skipped move leaves its slot empty, black's reply stays black's
+        assert!(game.turns[1].white_move.is_none());
+        assert_eq!(game.turns[1].black_move.as_ref().unwrap().full_str, "Nc6");
+        assert_eq!(game.turns[2].white_move.as_ref().unwrap().full_str, "Bb5");
+        assert_eq!(game.turns[2].black_move.as_ref().unwrap().full_str, "a6");
+
+        // no made-up problems with the moves after it
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].kind, DiagnosticKind::Syntax);
+    }
+
+    // an illegal move is skipped the same way
+    let (game, diagnostics) = parse_pgn_lenient("1.e2e4 e7e4 2.d2d4 d7d5 *");
+    assert!(game.turns[0].black_move.is_none());
+    assert_eq!(game.turns[1].black_move.as_ref().unwrap().full_str, "d7d5");
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].kind, DiagnosticKind::IllegalMove);
+}
@@ -0,0 +1,155 @@
+use chess_parser::{
+    parse_pgn, parse_pgn_with_options, ParseOptions, PgnReader, Piece, PieceLetters,
+};
+use std::io::Cursor;
+
+fn with_letters(piece_letters: PieceLetters) -> ParseOptions {
+    ParseOptions {
+        piece_letters,
+        validate_moves: true,
+        ..ParseOptions::default()
+    }
+}
+
+fn main_line(text: &str, letters: PieceLetters) -> Vec<String> {
+    let game = parse_pgn_with_options(text, &with_letters(letters)).unwrap();
+    game.uci_moves().unwrap()
+}
+
+#[test]
+fn test_localized_letters() {
+    let english = main_line(
+        "1.e4 e5 2.Nf3 Nc6 3.Bb5 a6 4.O-O Nf6 5.Re1 Qe7 *",
+        PieceLetters::ENGLISH,
+    );
+    let german = main_line(
+        "1.e4 e5 2.Sf3 Sc6 3.Lb5 a6 4.O-O Sf6 5.Te1 De7 *",
+        PieceLetters::GERMAN,
+    );
+    let french = main_line(
+        "1.e4 e5 2.Cf3 Cc6 3.Fb5 a6 4.O-O Cf6 5.Te1 De7 *",
+        PieceLetters::FRENCH,
+    );
+    assert_eq!(german, english);
+    assert_eq!(french, english);
+
+    // the move model keeps English letters
+    let game =
+        parse_pgn_with_options("1.e4 e5 2.Sf3 *", &with_letters(PieceLetters::GERMAN)).unwrap();
+    let knight = game.turns[1].white_move.as_ref().unwrap();
+    assert_eq!(knight.piece, Piece::Knight);
+    assert_eq!(knight.full_str, "Nf3");
+
+    // French "R" is the king, not a rook
+    let game =
+        parse_pgn_with_options("1.e4 e5 2.Re2 *", &with_letters(PieceLetters::FRENCH)).unwrap();
+    assert_eq!(
+        game.turns[1].white_move.as_ref().unwrap().piece,
+        Piece::King
+    );
+}
+
+#[test]
+fn test_figurines() {
+    let english = main_line("1.e4 e5 2.Nf3 Nc6 3.Bc4 Bc5 *", PieceLetters::ENGLISH);
+    // white and black figurines alike
+    let figurine = main_line("1.e4 e5 2.♘f3 ♞c6 3.♗c4 ♝c5 *", PieceLetters::FIGURINE);
+    assert_eq!(figurine, english);
+
+    // a move right after a comment
+    let figurine = main_line(
+        "1.e4 e5 2.{c}♘f3 ♞c6 3.♗c4 {c}♝c5 *",
+        PieceLetters::FIGURINE,
+    );
+    assert_eq!(figurine, english);
+
+    let text = "[FEN \"8/4P3/8/8/8/8/k7/4K3 w - - 0 1\"]\n\n1.e8=♕ *";
+    let game = parse_pgn_with_options(text, &with_letters(PieceLetters::FIGURINE)).unwrap();
+    assert_eq!(
+        game.turns[0].white_move.as_ref().unwrap().promotion,
+        Some(Piece::Queen)
+    );
+}
+
+#[test]
+fn test_tags_and_comments_keep_their_letters() {
+    let text = "[White \"Schmidt, Dieter\"]\n[Black \"Lange, Tobias\"]\n\n1.e4 {Sizilianisch? Lieber nicht} e5 ; Dame\n2.Sf3 *";
+    let game = parse_pgn_with_options(text, &with_letters(PieceLetters::GERMAN)).unwrap();
+    assert_eq!(game.metadata["White"], "Schmidt, Dieter");
+    assert_eq!(game.metadata["Black"], "Lange, Tobias");
+    let white = game.turns[0].white_move.as_ref().unwrap();
+    assert_eq!(white.comments, ["Sizilianisch? Lieber nicht"]);
+    assert_eq!(
+        game.turns[0].black_move.as_ref().unwrap().comments,
+        ["Dame"]
+    );
+}
+
+#[test]
+fn test_errors_point_into_the_original_text() {
+    let text = "1.e4 e5 2.♘f3 ♞c6 3.♘g5 ♘x *";
+    let error = parse_pgn_with_options(text, &with_letters(PieceLetters::FIGURINE)).unwrap_err();
+    // the knight has no square after it - columns count the figurines as one character
+    assert_eq!(error.position(), Some((1, 26)));
+}
+
+#[test]
+fn test_writing_with_letters() {
+    let game = parse_pgn(
+        "1.e4 e5 2.Nf3 Nc6 3.Bb5 a6 4.O-O Nf6 5.d4 exd4 6.e5 d3 7.exf6 dxc2 8.fxg7 cxb1=Q *",
+    )
+    .unwrap();
+    let german = game.to_pgn_with_letters(&PieceLetters::GERMAN);
+    assert_eq!(
+        german,
+        "1. e4 e5 2. Sf3 Sc6 3. Lb5 a6 4. O-O Sf6 5. d4 exd4 6. e5 d3 7. exf6 dxc2 8.\nfxg7 cxb1=D *\n"
+    );
+    let reparsed = parse_pgn_with_options(&german, &with_letters(PieceLetters::GERMAN)).unwrap();
+    assert_eq!(reparsed.to_pgn(), game.to_pgn());
+
+    let figurine = game.to_pgn_with_letters(&PieceLetters::FIGURINE);
+    assert!(figurine.contains("2. ♘f3 ♘c6 3. ♗b5"));
+    let reparsed =
+        parse_pgn_with_options(&figurine, &with_letters(PieceLetters::FIGURINE)).unwrap();
+    assert_eq!(reparsed.to_pgn(), game.to_pgn());
+}
+
+#[test]
+fn test_letter_sets() {
+    assert_eq!("german".parse::<PieceLetters>(), Ok(PieceLetters::GERMAN));
+    assert_eq!("FR".parse::<PieceLetters>(), Ok(PieceLetters::FRENCH));
+    assert!("klingon".parse::<PieceLetters>().is_err());
+    assert_eq!(PieceLetters::default(), PieceLetters::ENGLISH);
+
+    // a custom set, e.g. Spanish
+    let spanish = PieceLetters::new('R', 'D', 'T', 'A', 'C', 'P');
+    assert_eq!(spanish.letter(Piece::Bishop), 'A');
+    assert_eq!(spanish.piece('C'), Some(Piece::Knight));
+    assert_eq!(spanish.translate_move("Bxe5+"), "Axe5+");
+    assert_eq!(spanish.translate_move("O-O-O"), "O-O-O");
+
+    let games: Vec<_> = PgnReader::with_options(
+        Cursor::new("1.e4 e5 2.Ac4 Cc6 *\n\n1.d4 Cf6 *\n"),
+        with_letters(spanish),
+    )
+    .collect();
+    assert_eq!(games.len(), 2);
+    assert!(games.iter().all(Result::is_ok));
+}
+
+#[test]
+fn test_tags_and_comments_read_alike_by_every_pass() {
+    // quotes, parentheses, brackets and results inside tag values and comments are not movetext
+    let parens = "(".repeat(40);
+    let text = format!(
+        "[Event \"Sieg 1-0 \\\" {} [\"]\n\n1.e4 {{ \" ; 0-1 {} }} e5 2.Sf3 ; \" [Site]\n*\n\n[Event \"B\"]\n\n1.d4 Sf6 *\n",
+        parens, parens
+    );
+    let games: Vec<_> =
+        PgnReader::with_options(Cursor::new(text), with_letters(PieceLetters::GERMAN)).collect();
+    assert_eq!(games.len(), 2);
+    let first = games[0].as_ref().unwrap();
+    assert_eq!(first.metadata["Event"], format!("Sieg 1-0 \" {} [", parens));
+    assert_eq!(first.uci_moves().unwrap(), ["e2e4", "e7e5", "g1f3"]);
+    assert!(games[1].is_ok());
+}
@@ -0,0 +1,150 @@
+use chess_parser::{
+    parse_pgn, parse_pgn_with_options, CastlingSide, Disambiguation, MoveNotation, ParseOptions,
+    PgnError, Piece,
+};
+
+fn only_notation(notation: MoveNotation) -> ParseOptions {
+    ParseOptions {
+        notation: Some(notation),
+        validate_moves: true,
+        ..ParseOptions::default()
+    }
+}
+
+#[test]
+fn test_long_algebraic_moves() {
+    let game = parse_pgn("1.e2-e4 d7-d5 2.e4xd5 Ng8-f6 3.Bf1-b5+ c7-c6 *").unwrap();
+    assert_eq!(game.turns.len(), 3);
+
+    let pawn = game.turns[0].white_move.as_ref().unwrap();
+    assert_eq!(pawn.notation, MoveNotation::Long);
+    assert_eq!(pawn.piece, Piece::Pawn);
+    assert_eq!(
+        pawn.disambig,
+        Some(Disambiguation::Square("e2".parse().unwrap()))
+    );
+    assert_eq!(pawn.target(), Some("e4".parse().unwrap()));
+
+    let capture = game.turns[1].white_move.as_ref().unwrap();
+    assert!(capture.capture);
+    assert_eq!(capture.target(), Some("d5".parse().unwrap()));
+
+    let knight = game.turns[1].black_move.as_ref().unwrap();
+    assert_eq!(knight.piece, Piece::Knight);
+    assert_eq!(
+        knight.disambig,
+        Some(Disambiguation::Square("g8".parse().unwrap()))
+    );
+
+    let check = game.turns[2].white_move.as_ref().unwrap();
+    assert_eq!(check.piece, Piece::Bishop);
+    assert!(check.check);
+
+    let game = parse_pgn("[FEN \"8/4P3/8/8/8/8/k7/4K3 w - - 0 1\"]\n\n1.e7-e8=Q *").unwrap();
+    let promotion = game.turns[0].white_move.as_ref().unwrap();
+    assert_eq!(promotion.promotion, Some(Piece::Queen));
+}
+
+#[test]
+fn test_uci_moves_are_resolved_on_the_board() {
+    let game = parse_pgn("1.e2e4 e7e5 2.g1f3 b8c6 3.f1c4 g8f6 4.e1g1 f6e4 5.c4f7 e8f7 *").unwrap();
+    // "1.e2e4 e7e5" is one turn, not squares run together
+    assert_eq!(game.turns.len(), 5);
+
+    let knight = game.turns[1].white_move.as_ref().unwrap();
+    assert_eq!(knight.notation, MoveNotation::Uci);
+    assert_eq!(knight.piece, Piece::Knight);
+    assert!(!knight.capture);
+
+    let castle = game.turns[3].white_move.as_ref().unwrap();
+    assert!(castle.special);
+    assert_eq!(castle.castling, Some(CastlingSide::Kingside));
+    assert_eq!(castle.piece, Piece::King);
+    assert_eq!(castle.target(), None);
+    assert_eq!(castle.full_str, "e1g1");
+
+    let capture = game.turns[3].black_move.as_ref().unwrap();
+    assert_eq!(capture.piece, Piece::Knight);
+    assert!(capture.capture);
+
+    let king = game.turns[4].black_move.as_ref().unwrap();
+    assert_eq!(king.piece, Piece::King);
+    assert!(king.capture);
+
+    let game = parse_pgn("[FEN \"8/4P3/8/8/8/8/k7/4K3 w - - 0 1\"]\n\n1.e7e8n *").unwrap();
+    let promotion = game.turns[0].white_move.as_ref().unwrap();
+    assert_eq!(promotion.promotion, Some(Piece::Knight));
+    assert_eq!(game.uci_moves().unwrap(), ["e7e8n"]);
+}
+
+#[test]
+fn test_illegal_uci_move() {
+    // a game with UCI moves is replayed to resolve them, even when moves aren't validated
+    let error = parse_pgn("1.e2e4 e7e4 *").unwrap_err();
+    assert!(matches!(error, PgnError::IllegalMove { .. }));
+}
+
+#[test]
+fn test_notation_selection() {
+    let text = "1.e4 e5 2.Nf3 *";
+    assert!(parse_pgn_with_options(text, &only_notation(MoveNotation::San)).is_ok());
+    let error = parse_pgn_with_options(text, &only_notation(MoveNotation::Uci)).unwrap_err();
+    assert!(matches!(
+        error,
+        PgnError::Syntax { line: 1, column: 3, ref token, ref expected }
+            if token == "e4" && expected == "a move in UCI notation"
+    ));
+
+    // castling is written the same way in long algebraic notation
+    let text = "1.e2-e4 e7-e5 2.Ng1-f3 Nb8-c6 3.Bf1-c4 Ng8-f6 4.O-O (4.Nf3-g5) 4...Nf6xe4 *";
+    assert!(parse_pgn_with_options(text, &only_notation(MoveNotation::Long)).is_ok());
+
+    // moves in variations count too
+    let text = "1.e2e4 (1.d4) 1...e7e5 *";
+    let error = parse_pgn_with_options(text, &only_notation(MoveNotation::Uci)).unwrap_err();
+    assert!(matches!(error, PgnError::Syntax { ref token, .. } if token == "d4"));
+}
+
+#[test]
+fn test_uci_moves_of_a_game() {
+    let game = parse_pgn("1.e4 e5 2.Nf3 Nc6 3.Bc4 Nf6 4.O-O Nxe4 (4...Be7) 5.Bxf7+ 1-0").unwrap();
+    assert_eq!(
+        game.uci_moves().unwrap(),
+        ["e2e4", "e7e5", "g1f3", "b8c6", "f1c4", "g8f6", "e1g1", "f6e4", "c4f7"]
+    );
+
+    let game = parse_pgn("1.e4 e5 2.Ke3 *").unwrap();
+    assert!(game.uci_moves().is_err());
+}
+
+#[test]
+fn test_coordinate_moves_round_trip() {
+    let text = "1.e2e4 e7e5 2.Ng1-f3 b8c6 3.f1b5 (3.f1c4 f8c5) 3...a7a6 4.e1g1 *";
+    let game = parse_pgn(text).unwrap();
+    let reparsed = parse_pgn(&game.to_pgn()).unwrap();
+    assert_eq!(reparsed.uci_moves().unwrap(), game.uci_moves().unwrap());
+    assert_eq!(reparsed.to_pgn(), game.to_pgn());
+    let castle = reparsed.turns[3].white_move.as_ref().unwrap();
+    assert_eq!(castle.castling, Some(CastlingSide::Kingside));
+}
+
+#[test]
+fn test_piece_capture_from_a_square_is_san() {
+    // a full-square origin with a capture is valid SAN, and long algebraic too
+    let text = "1.e4 e5 2.Qh5 Nc6 3.Qh5xf7+ Kxf7 *";
+    let game = parse_pgn_with_options(text, &only_notation(MoveNotation::San)).unwrap();
+    let capture = game.turns[2].white_move.as_ref().unwrap();
+    assert_eq!(capture.notation, MoveNotation::San);
+    assert_eq!(
+        capture.disambig,
+        Some(Disambiguation::Square("h5".parse().unwrap()))
+    );
+
+    let text = "1.e2-e4 e7-e5 2.Qd1-h5 Nb8-c6 3.Qh5xf7+ Ke8xf7 *";
+    assert!(parse_pgn_with_options(text, &only_notation(MoveNotation::Long)).is_ok());
+
+    // a pawn capture from a square is only long algebraic
+    let text = "1.e4 d5 2.e4xd5 *";
+    let error = parse_pgn_with_options(text, &only_notation(MoveNotation::San)).unwrap_err();
+    assert!(matches!(error, PgnError::Syntax { ref token, .. } if token == "e4xd5"));
+}
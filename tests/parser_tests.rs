@@ -1,4 +1,12 @@
-use chess_parser::parse_pgn;
+#![allow(clippy::bool_assert_comparison, clippy::needless_borrow)]
+
+use chess_parser::{
+    nag_from_suffix, nag_meaning, nag_to_suffix, parse_pgn, parse_pgn_database,
+    parse_pgn_database_lenient, parse_pgn_with_options, replay_game, split_pgn_games, CastlingSide,
+    ChessGame, Disambiguation, File, GameResult, ParseOptions, PgnError, PgnReader, Piece, Rank,
+    Tags,
+};
+use std::io::Cursor;
 
 #[test]
 fn test_parse_simple_game() {
@@ -13,9 +21,9 @@ fn test_parse_simple_game() {
 
     match parse_pgn(pgn_data) {
         Ok(game) => {
-            assert_eq!(game.metadata.get("Event"), Some(&"Simple Game".to_string()));
+            assert_eq!(game.metadata.get("Event"), Some("Simple Game"));
             assert_eq!(game.turns.len(), 3);
-            assert_eq!(game.result, "1-0");
+            assert_eq!(game.result, GameResult::WhiteWins);
         }
         Err(e) => panic!("Parsing failed: {}", e),
     }
@@ -34,12 +42,12 @@ fn test_parse_with_promotion() {
 
     match parse_pgn(pgn_data) {
         Ok(game) => {
-            assert_eq!(game.result, "0-1");
+            assert_eq!(game.result, GameResult::BlackWins);
 
             let mv_prom = game.turns[2].white_move.as_ref().unwrap();
             let mv_nopr = game.turns[2].black_move.as_ref().unwrap();
-            assert_eq!(mv_prom.promotion, 'Q');
-            assert_eq!(mv_nopr.promotion, ' ');
+            assert_eq!(mv_prom.promotion, Some(Piece::Queen));
+            assert_eq!(mv_nopr.promotion, None);
         }
         Err(e) => panic!("Parsing failed: {}", e),
     }
@@ -63,18 +71,42 @@ fn test_parse_castling() {
 
             assert_eq!(white_move.full_str, "O-O");
             assert_eq!(white_move.special, true);
-            assert_eq!(white_move.promotion, ' ');
+            assert_eq!(white_move.castling, Some(CastlingSide::Kingside));
+            assert_eq!(white_move.promotion, None);
             assert_eq!(white_move.annotation, "");
 
             assert_eq!(black_move.full_str, "O-O-O!!");
             assert_eq!(black_move.special, true);
-            assert_eq!(black_move.promotion, ' ');
+            assert_eq!(black_move.castling, Some(CastlingSide::Queenside));
+            assert_eq!(black_move.promotion, None);
             assert_eq!(black_move.annotation, "!!");
         }
         Err(e) => panic!("Parsing failed: {}", e),
     }
 }
 
+#[test]
+fn test_castling_with_zeros() {
+    let game = parse_pgn("1.e4 e5 2.Nf3 Nf6 3.Bc4 Bc5 4.0-0 0-0+ 0-1").unwrap();
+    let white_move = game.turns[3].white_move.as_ref().unwrap();
+    let black_move = game.turns[3].black_move.as_ref().unwrap();
+    assert_eq!(white_move.full_str, "0-0");
+    assert_eq!(white_move.castling, Some(CastlingSide::Kingside));
+    assert_eq!(black_move.castling, Some(CastlingSide::Kingside));
+    assert!(black_move.check);
+    assert_eq!(game.result, GameResult::BlackWins);
+
+    let game = parse_pgn("1.d4 d5 2.Nc3 Nc6 3.Bf4 Bf5 4.Qd2 Qd7 5.0-0-0 O-O-O 1/2-1/2").unwrap();
+    assert_eq!(
+        game.turns[4].white_move.as_ref().unwrap().castling,
+        Some(CastlingSide::Queenside)
+    );
+    assert_eq!(replay_game(&game).unwrap()[8].to.to_string(), "c1");
+
+    // a move without castling has no side
+    assert_eq!(game.turns[0].white_move.as_ref().unwrap().castling, None);
+}
+
 #[test]
 fn test_parse_en_passant() {
     let pgn_data = r#"
@@ -91,17 +123,59 @@ fn test_parse_en_passant() {
             let mv = game.turns[2].white_move.as_ref().unwrap();
             assert_eq!(mv.full_str, "dxc6");
             assert_eq!(mv.capture, true);
-            assert_eq!(mv.piece, 'p');
+            assert_eq!(mv.piece, Piece::Pawn);
             assert_eq!(mv.special, false);
             assert_eq!(mv.annotation, "");
-            assert_eq!(mv.loc_col, 'c');
-            assert_eq!(mv.loc_row, 6);
-            assert_eq!(mv.disambig, "d");
+            assert_eq!(mv.loc_col, File::from_char('c'));
+            assert_eq!(mv.loc_row, Rank::from_char('6'));
+            assert_eq!(mv.disambig, "d".parse().ok());
         }
         Err(e) => panic!("Parsing failed: {}", e),
     }
 }
 
+#[test]
+fn test_en_passant_suffix() {
+    for pgn_data in [
+        "1.e4 Nf6 2.e5 d5 3.exd6 e.p. Ng8 *",
+        "1.e4 Nf6 2.e5 d5 3.exd6e.p. Ng8 *",
+        "1.e4 Nf6 2.e5 d5 3.exd6+ e.p. Ng8 *",
+        "1.e4 Nf6 2.e5 d5 3.exd6 e.p.+ Ng8 *",
+    ] {
+        let game = parse_pgn(pgn_data).unwrap();
+        let mv = game.turns[2].white_move.as_ref().unwrap();
+        assert!(mv.en_passant_suffix);
+        // the suffix is recorded apart from the move
+        assert!(mv.full_str.starts_with("exd6"));
+        assert!(!mv.full_str.contains("e.p."));
+        assert_eq!(game.turns[2].black_move.as_ref().unwrap().full_str, "Ng8");
+    }
+
+    let game = parse_pgn("1.e4 Nf6 2.e5 d5 3.exd6 e.p.! Ng8 *").unwrap();
+    let mv = game.turns[2].white_move.as_ref().unwrap();
+    assert_eq!(mv.full_str, "exd6!");
+    assert_eq!(mv.quality, "!");
+    assert!(game.to_pgn().contains("3. exd6! e.p. Ng8"));
+}
+
+#[test]
+fn test_null_moves() {
+    let game = parse_pgn("1.e4 -- 2.d4 Z0 (2...e5) 3.Nf3 *").unwrap();
+    for mv in [
+        game.turns[0].black_move.as_ref().unwrap(),
+        game.turns[1].black_move.as_ref().unwrap(),
+    ] {
+        assert!(mv.null_move);
+        assert_eq!(mv.target(), None);
+        assert!(!mv.special);
+    }
+    assert_eq!(game.turns[1].black_move.as_ref().unwrap().full_str, "Z0");
+    assert!(!game.turns[1].white_move.as_ref().unwrap().null_move);
+    assert!(game
+        .to_pgn()
+        .contains("1. e4 -- 2. d4 Z0 (2... e5) 3. Nf3 *"));
+}
+
 #[test]
 fn test_whitespace() {
     let pgn_text = "1. e4   e5   2.Nf3 Nc6\n3. Bb5 a6 1-0";
@@ -134,8 +208,8 @@ fn test_chess_game() {
     1.e4 e5 2.Nf3 Nc6 3.Bb5 a6 1-0
     "#;
     let game = parse_pgn(pgn_text).unwrap();
-    assert_eq!(game.metadata.get("Event"), Some(&"Test Game".to_string()));
-    assert_eq!(game.result, "1-0");
+    assert_eq!(game.metadata.get("Event"), Some("Test Game"));
+    assert_eq!(game.result, GameResult::WhiteWins);
     assert_eq!(game.turns.len(), 3);
 }
 
@@ -155,12 +229,12 @@ fn test_chess_move() {
 
     let move_info = turn.white_move.as_ref().unwrap();
     assert_eq!(move_info.full_str, "Nf3+");
-    assert_eq!(move_info.piece, 'N');
+    assert_eq!(move_info.piece, Piece::Knight);
     assert_eq!(move_info.capture, false);
     assert_eq!(move_info.special, false);
-    assert_eq!(move_info.disambig, "");
-    assert_eq!(move_info.loc_col, 'f');
-    assert_eq!(move_info.loc_row, 3);
+    assert_eq!(move_info.disambig, None);
+    assert_eq!(move_info.loc_col, File::from_char('f'));
+    assert_eq!(move_info.loc_row, Rank::from_char('3'));
     assert_eq!(move_info.annotation, "+");
 }
 
@@ -173,20 +247,20 @@ fn test_pawn_move() {
     let move_white = turn.white_move.as_ref().unwrap();
     let move_black = turn.black_move.as_ref().unwrap();
     assert_eq!(move_white.full_str, "e4");
-    assert_eq!(move_white.piece, 'p');
+    assert_eq!(move_white.piece, Piece::Pawn);
     assert_eq!(move_white.special, false);
-    assert_eq!(move_white.promotion, ' ');
-    assert_eq!(move_white.loc_col, 'e');
-    assert_eq!(move_white.loc_row, 4);
-    assert_eq!(move_white.disambig, "");
+    assert_eq!(move_white.promotion, None);
+    assert_eq!(move_white.loc_col, File::from_char('e'));
+    assert_eq!(move_white.loc_row, Rank::from_char('4'));
+    assert_eq!(move_white.disambig, None);
 
     assert_eq!(move_black.full_str, "e8=Q");
-    assert_eq!(move_black.piece, 'p');
+    assert_eq!(move_black.piece, Piece::Pawn);
     assert_eq!(move_black.special, false);
-    assert_eq!(move_black.promotion, 'Q');
-    assert_eq!(move_black.loc_col, 'e');
-    assert_eq!(move_black.loc_row, 8);
-    assert_eq!(move_black.disambig, "");
+    assert_eq!(move_black.promotion, Some(Piece::Queen));
+    assert_eq!(move_black.loc_col, File::from_char('e'));
+    assert_eq!(move_black.loc_row, Rank::from_char('8'));
+    assert_eq!(move_black.disambig, None);
 }
 
 #[test]
@@ -200,20 +274,20 @@ fn test_pawn_capture() {
     assert_eq!(white_move.full_str, "exd5");
     assert_eq!(white_move.capture, true);
     assert_eq!(white_move.special, false);
-    assert_eq!(white_move.piece, 'p');
-    assert_eq!(white_move.disambig, "e");
-    assert_eq!(white_move.promotion, ' ');
-    assert_eq!(white_move.loc_col, 'd');
-    assert_eq!(white_move.loc_row, 5);
+    assert_eq!(white_move.piece, Piece::Pawn);
+    assert_eq!(white_move.disambig, "e".parse().ok());
+    assert_eq!(white_move.promotion, None);
+    assert_eq!(white_move.loc_col, File::from_char('d'));
+    assert_eq!(white_move.loc_row, Rank::from_char('5'));
 
     assert_eq!(black_move.full_str, "exf8=N");
     assert_eq!(black_move.capture, true);
     assert_eq!(black_move.special, false);
-    assert_eq!(black_move.piece, 'p');
-    assert_eq!(black_move.disambig, "e");
-    assert_eq!(black_move.promotion, 'N');
-    assert_eq!(black_move.loc_col, 'f');
-    assert_eq!(black_move.loc_row, 8);
+    assert_eq!(black_move.piece, Piece::Pawn);
+    assert_eq!(black_move.disambig, "e".parse().ok());
+    assert_eq!(black_move.promotion, Some(Piece::Knight));
+    assert_eq!(black_move.loc_col, File::from_char('f'));
+    assert_eq!(black_move.loc_row, Rank::from_char('8'));
 }
 
 #[test]
@@ -223,12 +297,28 @@ fn test_piece_symbols() {
     let turns = game.turns;
 
     assert_eq!(turns.len(), 3);
-    assert_eq!(turns[0].white_move.as_ref().unwrap().piece, 'K');
-    assert_eq!(turns[0].black_move.as_ref().unwrap().piece, 'Q');
-    assert_eq!(turns[1].white_move.as_ref().unwrap().piece, 'R');
-    assert_eq!(turns[1].black_move.as_ref().unwrap().piece, 'B');
-    assert_eq!(turns[2].white_move.as_ref().unwrap().piece, 'N');
-    assert_eq!(turns[2].black_move.as_ref().unwrap().piece, 'p');
+    assert_eq!(turns[0].white_move.as_ref().unwrap().piece, Piece::King);
+    assert_eq!(turns[0].black_move.as_ref().unwrap().piece, Piece::Queen);
+    assert_eq!(turns[1].white_move.as_ref().unwrap().piece, Piece::Rook);
+    assert_eq!(turns[1].black_move.as_ref().unwrap().piece, Piece::Bishop);
+    assert_eq!(turns[2].white_move.as_ref().unwrap().piece, Piece::Knight);
+    assert_eq!(turns[2].black_move.as_ref().unwrap().piece, Piece::Pawn);
+}
+
+#[test]
+fn test_explicit_pawn_letter() {
+    let game = parse_pgn("1.Pe4 d5 2.Pexd5 1-0").unwrap();
+    let advance = game.turns[0].white_move.as_ref().unwrap();
+    assert_eq!(advance.piece, Piece::Pawn);
+    assert_eq!(advance.target(), "e4".parse().ok());
+    let capture = game.turns[1].white_move.as_ref().unwrap();
+    assert_eq!(capture.piece, Piece::Pawn);
+    assert!(capture.capture);
+    assert_eq!(capture.disambig, "e".parse().ok());
+    assert_eq!(replay_game(&game).unwrap().len(), 3);
+
+    // pawns can't promote to pawns
+    assert!(parse_pgn("1.e8=P 1-0").is_err());
 }
 
 #[test]
@@ -237,10 +327,22 @@ fn test_location() {
     let game = parse_pgn(pgn_text).unwrap();
     let turn = &game.turns[0];
 
-    assert_eq!(turn.white_move.as_ref().unwrap().loc_col, 'e');
-    assert_eq!(turn.white_move.as_ref().unwrap().loc_row, 4);
-    assert_eq!(turn.black_move.as_ref().unwrap().loc_col, 'h');
-    assert_eq!(turn.black_move.as_ref().unwrap().loc_row, 8);
+    assert_eq!(
+        turn.white_move.as_ref().unwrap().loc_col,
+        File::from_char('e')
+    );
+    assert_eq!(
+        turn.white_move.as_ref().unwrap().loc_row,
+        Rank::from_char('4')
+    );
+    assert_eq!(
+        turn.black_move.as_ref().unwrap().loc_col,
+        File::from_char('h')
+    );
+    assert_eq!(
+        turn.black_move.as_ref().unwrap().loc_row,
+        Rank::from_char('8')
+    );
 }
 
 #[test]
@@ -254,19 +356,19 @@ fn test_capture() {
 
     assert_eq!(white_move.capture, true);
     assert_eq!(white_move.special, false);
-    assert_eq!(white_move.piece, 'p');
-    assert_eq!(white_move.promotion, 'Q');
-    assert_eq!(white_move.loc_col, 'e');
-    assert_eq!(white_move.loc_row, 5);
-    assert_eq!(white_move.disambig, "d");
+    assert_eq!(white_move.piece, Piece::Pawn);
+    assert_eq!(white_move.promotion, Some(Piece::Queen));
+    assert_eq!(white_move.loc_col, File::from_char('e'));
+    assert_eq!(white_move.loc_row, Rank::from_char('5'));
+    assert_eq!(white_move.disambig, "d".parse().ok());
 
     assert_eq!(black_move.capture, true);
     assert_eq!(black_move.special, false);
-    assert_eq!(black_move.piece, 'Q');
-    assert_eq!(black_move.promotion, ' ');
-    assert_eq!(black_move.loc_col, 'd');
-    assert_eq!(black_move.loc_row, 8);
-    assert_eq!(black_move.disambig, "d5");
+    assert_eq!(black_move.piece, Piece::Queen);
+    assert_eq!(black_move.promotion, None);
+    assert_eq!(black_move.loc_col, File::from_char('d'));
+    assert_eq!(black_move.loc_row, Rank::from_char('8'));
+    assert_eq!(black_move.disambig, "d5".parse().ok());
 }
 
 #[test]
@@ -274,9 +376,15 @@ fn test_promotion() {
     let pgn_text = "1.e8=Q e7 2. e6=B 1-0";
     let game = parse_pgn(pgn_text).unwrap();
 
-    assert_eq!(game.turns[0].white_move.as_ref().unwrap().promotion, 'Q');
-    assert_eq!(game.turns[0].black_move.as_ref().unwrap().promotion, ' ');
-    assert_eq!(game.turns[1].white_move.as_ref().unwrap().promotion, 'B');
+    assert_eq!(
+        game.turns[0].white_move.as_ref().unwrap().promotion,
+        Some(Piece::Queen)
+    );
+    assert_eq!(game.turns[0].black_move.as_ref().unwrap().promotion, None);
+    assert_eq!(
+        game.turns[1].white_move.as_ref().unwrap().promotion,
+        Some(Piece::Bishop)
+    );
 }
 
 #[test]
@@ -294,13 +402,13 @@ fn test_annotation() {
 fn test_game_termination() {
     let pgn_text = "1.e4 1-0";
     let game = parse_pgn(pgn_text).unwrap();
-    assert_eq!(game.result, "1-0");
+    assert_eq!(game.result, GameResult::WhiteWins);
     assert_eq!(game.turns.len(), 1);
     assert_eq!(game.turns[0].black_move.as_ref().unwrap().full_str, "");
 
     let pgn_text2 = "1.e4 e5 1-0";
     let game2 = parse_pgn(pgn_text2).unwrap();
-    assert_eq!(game2.result, "1-0");
+    assert_eq!(game2.result, GameResult::WhiteWins);
     assert_eq!(game2.turns.len(), 1);
     assert_eq!(game2.turns[0].black_move.as_ref().unwrap().full_str, "e5");
 }
@@ -314,15 +422,15 @@ fn test_game_results() {
     let game2 = parse_pgn(pgn_text2).unwrap();
     let game3 = parse_pgn(pgn_text3).unwrap();
 
-    assert_eq!(game1.result, "1-0");
-    assert_eq!(game2.result, "0-1");
-    assert_eq!(game3.result, "1/2-1/2");
+    assert_eq!(game1.result, GameResult::WhiteWins);
+    assert_eq!(game2.result, GameResult::BlackWins);
+    assert_eq!(game3.result, GameResult::Draw);
 }
 
 #[test]
 fn test_columns_and_rows() {
     let columns = ['a', 'b', 'c', 'd', 'e', 'f', 'g', 'h'];
-    let rows = [1, 2, 3, 4, 5, 6, 7, 8];
+    let rows = ['1', '2', '3', '4', '5', '6', '7', '8'];
     for &col in &columns {
         for &row in &rows {
             let location = format!("{}{}", col, row);
@@ -331,8 +439,9 @@ fn test_columns_and_rows() {
             let mv = &game.turns[0].white_move.as_ref().unwrap();
 
             assert_eq!(mv.full_str, location);
-            assert_eq!(mv.loc_col, col);
-            assert_eq!(mv.loc_row, row);
+            assert_eq!(mv.loc_col, File::from_char(col));
+            assert_eq!(mv.loc_row, Rank::from_char(row));
+            assert_eq!(mv.target().unwrap().to_string(), location);
         }
     }
 }
@@ -342,10 +451,29 @@ fn test_move_disambiguation() {
     let pgn_text = "1.Nbd7 N1f6 2. Qb5b6 Re8 1-0";
     let game = parse_pgn(pgn_text).unwrap();
 
-    assert_eq!(game.turns[0].white_move.as_ref().unwrap().disambig, "b");
-    assert_eq!(game.turns[0].black_move.as_ref().unwrap().disambig, "1");
-    assert_eq!(game.turns[1].white_move.as_ref().unwrap().disambig, "b5");
-    assert_eq!(game.turns[1].black_move.as_ref().unwrap().disambig, "");
+    assert_eq!(
+        game.turns[0].white_move.as_ref().unwrap().disambig,
+        Some(Disambiguation::File(File::from_char('b').unwrap()))
+    );
+    assert_eq!(
+        game.turns[0].black_move.as_ref().unwrap().disambig,
+        Some(Disambiguation::Rank(Rank::from_char('1').unwrap()))
+    );
+    assert_eq!(
+        game.turns[1].white_move.as_ref().unwrap().disambig,
+        Some(Disambiguation::Square("b5".parse().unwrap()))
+    );
+    assert_eq!(game.turns[1].black_move.as_ref().unwrap().disambig, None);
+
+    // a capturing pawn is disambiguated by its file
+    let game = parse_pgn("1.e4 d5 2.exd5 1-0").unwrap();
+    let capture = game.turns[1].white_move.as_ref().unwrap();
+    assert_eq!(
+        capture.disambig.map(|d| d.to_string()),
+        Some("e".to_string())
+    );
+    assert!(capture.disambig.unwrap().matches("e4".parse().unwrap()));
+    assert!(!capture.disambig.unwrap().matches("d4".parse().unwrap()));
 }
 
 #[test]
@@ -362,19 +490,13 @@ fn test_metadata() {
     1.e4 e5 1-0
     "#;
     let game = parse_pgn(pgn_text).unwrap();
-    assert_eq!(game.metadata.get("Event"), Some(&"Test Event".to_string()));
-    assert_eq!(game.metadata.get("Site"), Some(&"Test Site".to_string()));
-    assert_eq!(game.metadata.get("Date"), Some(&"2021.07.23".to_string()));
-    assert_eq!(game.metadata.get("Round"), Some(&"1".to_string()));
-    assert_eq!(
-        game.metadata.get("White"),
-        Some(&"White Player".to_string())
-    );
-    assert_eq!(
-        game.metadata.get("Black"),
-        Some(&"Black Player".to_string())
-    );
-    assert_eq!(game.result, "1-0");
+    assert_eq!(game.metadata.get("Event"), Some("Test Event"));
+    assert_eq!(game.metadata.get("Site"), Some("Test Site"));
+    assert_eq!(game.metadata.get("Date"), Some("2021.07.23"));
+    assert_eq!(game.metadata.get("Round"), Some("1"));
+    assert_eq!(game.metadata.get("White"), Some("White Player"));
+    assert_eq!(game.metadata.get("Black"), Some("Black Player"));
+    assert_eq!(game.result, GameResult::WhiteWins);
 }
 
 #[test]
@@ -523,3 +645,551 @@ fn test_invalid_metadata() {
         println!("Parsing error as expected: {}", e);
     }
 }
+
+#[test]
+fn test_escaped_tag_values() {
+    let pgn_text = r#"[Event "The \"Immortal\" Game"]
+[Site "C:\\games\\"]
+[Annotator "a \b"]
+
+1.e4 *"#;
+    let game = parse_pgn(pgn_text).unwrap();
+    assert_eq!(game.metadata.get("Event"), Some("The \"Immortal\" Game"));
+    assert_eq!(game.metadata.get("Site"), Some("C:\\games\\"));
+    // a backslash escaping nothing is kept
+    assert_eq!(game.metadata.get("Annotator"), Some("a \\b"));
+
+    // an escaped quote doesn't end the tag when splitting a database
+    let pgn_text = r#"[Event "1-0 \"]\" 0-1"] 1.e4 1-0 [Event "\\"] 1.d4 0-1"#;
+    let games = split_pgn_games(pgn_text);
+    assert_eq!(games.len(), 2);
+    assert_eq!(games[1].trim(), r#"[Event "\\"] 1.d4 0-1"#);
+    let game = parse_pgn(games[0]).unwrap();
+    assert_eq!(game.metadata.event(), Some("1-0 \"]\" 0-1"));
+}
+
+#[test]
+fn test_empty_and_padded_tag_values() {
+    let pgn_text = "[Event \"\"]\n[Site \" lead\"]\n[Round \"trail \"]\n\n1.e4 *";
+    let game = parse_pgn(pgn_text).unwrap();
+    assert_eq!(game.metadata.get("Event"), Some(""));
+    assert_eq!(game.metadata.get("Site"), Some(" lead"));
+    assert_eq!(game.metadata.get("Round"), Some("trail "));
+
+    // written back and read again unchanged
+    let reparsed = parse_pgn(&game.to_pgn()).unwrap();
+    assert_eq!(reparsed.metadata, game.metadata);
+    let tags: Tags = [("Event", ""), ("White", "  ")].into_iter().collect();
+    let game = ChessGame {
+        metadata: tags,
+        ..Default::default()
+    };
+    assert_eq!(parse_pgn(&game.to_pgn()).unwrap().metadata, game.metadata);
+}
+
+#[test]
+fn test_tag_key_characters() {
+    let pgn_text = r#"[Black_Team "B"]
+[WhiteFideId2 "123"]
+[Event "E"]
+
+1.e4 *"#;
+    let game = parse_pgn(pgn_text).unwrap();
+    assert_eq!(game.metadata.get("Black_Team"), Some("B"));
+    assert_eq!(game.metadata.get("WhiteFideId2"), Some("123"));
+
+    assert!(parse_pgn("[White Team \"B\"]\n1.e4 *").is_err());
+    assert!(parse_pgn("[Black-Team \"B\"]\n1.e4 *").is_err());
+}
+
+#[test]
+fn test_parse_database() {
+    let pgn_text = r#"
+    [Event "First"]
+    [Result "1-0"]
+
+    1.e4 e5 2.Nf3 1-0
+
+    [Event "Second"]
+    [Result "0-1"]
+
+    1.d4 d5 2.c4 dxc4 0-1
+    1.c4 1/2-1/2
+    "#;
+    let games = parse_pgn_database(pgn_text);
+    assert_eq!(games.len(), 3);
+
+    let first = games[0].as_ref().unwrap();
+    assert_eq!(first.metadata.get("Event"), Some("First"));
+    assert_eq!(first.turns.len(), 2);
+    assert_eq!(first.result, GameResult::WhiteWins);
+
+    let second = games[1].as_ref().unwrap();
+    assert_eq!(second.metadata.get("Event"), Some("Second"));
+    assert_eq!(second.turns.len(), 2);
+    assert_eq!(second.result, GameResult::BlackWins);
+
+    let third = games[2].as_ref().unwrap();
+    assert_eq!(third.metadata.len(), 0);
+    assert_eq!(third.result, GameResult::Draw);
+}
+
+#[test]
+fn test_parse_database_malformed_game() {
+    let pgn_text = r#"
+    [Event "Good"]
+    1.e4 e5 1-0
+    [Event "Bad"]
+    1.Ue4 e5 0-1
+    [Event "Missing result"]
+    1.e4 e5
+    [Event "Also good"]
+    1.d4 d5 1/2-1/2
+    "#;
+    let games = parse_pgn_database(pgn_text);
+    assert_eq!(games.len(), 4);
+    assert!(games[0].is_ok());
+    assert!(games[1].is_err());
+    assert!(games[2].is_err());
+
+    let last = games[3].as_ref().unwrap();
+    assert_eq!(last.metadata.get("Event"), Some("Also good"));
+    assert_eq!(last.result, GameResult::Draw);
+}
+
+#[test]
+fn test_parse_database_unclosed_comment() {
+    let pgn_text = "[Event \"Bad\"]\n\n1.e4 {never closed e5 1-0\n\n[Event \"Good\"]\n\n1.d4 d5 0-1\n\n[Event \"Also good\"]\n\n1.c4 1/2-1/2\n";
+    let games = parse_pgn_database(pgn_text);
+    assert_eq!(games.len(), 3);
+    assert!(games[0].is_err());
+    assert_eq!(games[1].as_ref().unwrap().result, GameResult::BlackWins);
+    assert_eq!(games[2].as_ref().unwrap().result, GameResult::Draw);
+
+    let (games, _) = parse_pgn_database_lenient(pgn_text, &ParseOptions::default());
+    let events: Vec<_> = games
+        .iter()
+        .filter_map(|game| game.metadata.get("Event"))
+        .collect();
+    assert!(events.ends_with(&["Good", "Also good"]));
+}
+
+#[test]
+fn test_split_games_ignores_results_in_tags() {
+    let pgn_text = "[Result \"1-0\"] [Event \"x 0-1\"] 1.e4 1-0 [Result \"0-1\"] 1.d4 d5 0-1";
+    let games = split_pgn_games(pgn_text);
+    assert_eq!(games.len(), 2);
+    assert_eq!(
+        games[0].trim(),
+        "[Result \"1-0\"] [Event \"x 0-1\"] 1.e4 1-0"
+    );
+    assert_eq!(games[1].trim(), "[Result \"0-1\"] 1.d4 d5 0-1");
+}
+
+#[test]
+fn test_pgn_reader() {
+    let pgn_text = "[Event \"First\"]\n\n1.e4 e5 1-0 1.d4\nd5 0-1\n\n[Event \"Bad\"]\n1.Ue4 1/2-1/2\n[Event \"Last\"]\n1.c4 1/2-1/2";
+    let games: Vec<_> = PgnReader::new(Cursor::new(pgn_text)).collect();
+    assert_eq!(games.len(), 4);
+
+    let first = games[0].as_ref().unwrap();
+    assert_eq!(first.metadata.get("Event"), Some("First"));
+    assert_eq!(first.result, GameResult::WhiteWins);
+
+    let second = games[1].as_ref().unwrap();
+    assert_eq!(second.turns.len(), 1);
+    assert_eq!(second.result, GameResult::BlackWins);
+
+    assert!(games[2].is_err());
+
+    let last = games[3].as_ref().unwrap();
+    assert_eq!(last.metadata.get("Event"), Some("Last"));
+    assert_eq!(last.result, GameResult::Draw);
+}
+
+#[test]
+fn test_pgn_reader_invalid_utf8() {
+    // a Latin-1 "é" in the first game doesn't stop the games after it
+    let mut pgn_bytes = b"[White \"Ren".to_vec();
+    pgn_bytes.push(0xE9);
+    pgn_bytes.extend_from_slice(b"\"]\n\n1.e4 e5 1-0\n\n[White \"Anna\"]\n\n1.d4 d5 0-1\n");
+    let games: Vec<_> = PgnReader::new(Cursor::new(pgn_bytes)).collect();
+    assert_eq!(games.len(), 2);
+
+    let first = games[0].as_ref().unwrap();
+    assert_eq!(first.metadata.get("White"), Some("Ren\u{FFFD}"));
+    let second = games[1].as_ref().unwrap();
+    assert_eq!(second.metadata.get("White"), Some("Anna"));
+}
+
+#[test]
+fn test_pgn_reader_unclosed_comment_and_tag() {
+    // a comment or tag value that is never closed ends at the next tag after a blank line
+    let pgn_text = "[Event \"One\"]\n\n1.e4 {oops e5 1-0\n\n[Event \"Two\"]\n\n1.d4 d5 0-1\n\n[Event \"bad]\n\n[Event \"Four\"]\n\n1.c4 c5 1/2-1/2\n";
+    let games: Vec<_> = PgnReader::new(Cursor::new(pgn_text)).collect();
+    assert_eq!(games.len(), 4);
+    assert!(games[0].is_err());
+    assert_eq!(
+        games[1].as_ref().unwrap().metadata.get("Event"),
+        Some("Two")
+    );
+    assert!(games[2].is_err());
+    assert_eq!(
+        games[3].as_ref().unwrap().metadata.get("Event"),
+        Some("Four")
+    );
+}
+
+#[test]
+fn test_pgn_reader_matches_split() {
+    let pgn_text = std::fs::read_to_string("examples/database.chess").unwrap();
+    let mut reader = PgnReader::new(Cursor::new(pgn_text.as_bytes()));
+    for game_text in split_pgn_games(&pgn_text) {
+        assert_eq!(reader.next_game_text().unwrap().unwrap(), game_text);
+    }
+    assert!(reader.next_game_text().is_none());
+}
+
+#[test]
+fn test_comments() {
+    let pgn_text = r#"
+    [Event "Comments"]
+
+    {Opening comment} 1.e4 {best by test} e5 ; classical reply
+    2.Nf3 Nc6 3. {pre-move} Bb5 a6 {end of game} 1-0
+    "#;
+    let game = parse_pgn(pgn_text).unwrap();
+    assert_eq!(game.turns.len(), 3);
+
+    let e4 = game.turns[0].white_move.as_ref().unwrap();
+    assert_eq!(e4.full_str, "e4");
+    assert_eq!(e4.pre_comments, vec!["Opening comment"]);
+    assert_eq!(e4.comments, vec!["best by test"]);
+
+    let e5 = game.turns[0].black_move.as_ref().unwrap();
+    assert_eq!(e5.full_str, "e5");
+    assert_eq!(e5.comments, vec!["classical reply"]);
+
+    let bb5 = game.turns[2].white_move.as_ref().unwrap();
+    assert_eq!(bb5.pre_comments, vec!["pre-move"]);
+    assert!(bb5.comments.is_empty());
+
+    let a6 = game.turns[2].black_move.as_ref().unwrap();
+    assert_eq!(a6.comments, vec!["end of game"]);
+}
+
+#[test]
+fn test_comments_in_game_term() {
+    let pgn_text = "1.e4 {first} e5 2.Nf3 {multi\nline} {second} 1-0";
+    let game = parse_pgn(pgn_text).unwrap();
+    let nf3 = game.turns[1].white_move.as_ref().unwrap();
+    assert_eq!(nf3.full_str, "Nf3");
+    assert_eq!(nf3.comments, vec!["multi\nline", "second"]);
+}
+
+#[test]
+fn test_split_games_ignores_results_in_comments() {
+    let pgn_text = "1.e4 {1-0 was expected [Event] here} e5 ; 0-1\n2.Nf3 1/2-1/2\n1.d4 0-1";
+    let games = split_pgn_games(pgn_text);
+    assert_eq!(games.len(), 2);
+    assert_eq!(parse_pgn(games[0]).unwrap().result, GameResult::Draw);
+    assert_eq!(parse_pgn(games[1]).unwrap().result, GameResult::BlackWins);
+}
+
+#[test]
+fn test_variations() {
+    let pgn_text = "1.e4 e5 2.Nf3 (2.Bc4 Nf6 (2...Bc5 3.Qh5) 3.d3) 2...Nc6 (2...d6 3.d4) 3.Bb5 1-0";
+    let game = parse_pgn(pgn_text).unwrap();
+    assert_eq!(game.turns.len(), 3);
+
+    let mainline: Vec<&str> = game
+        .mainline()
+        .iter()
+        .map(|mv| mv.full_str.as_str())
+        .collect();
+    assert_eq!(mainline, vec!["e4", "e5", "Nf3", "Nc6", "Bb5"]);
+
+    let nf3 = game.turns[1].white_move.as_ref().unwrap();
+    assert_eq!(nf3.variations.len(), 1);
+    let bc4_line = &nf3.variations[0];
+    assert_eq!(bc4_line.turns.len(), 2);
+    assert_eq!(bc4_line.turns[0].turn_number, 2);
+    assert_eq!(bc4_line.turns[1].turn_number, 3);
+    assert!(bc4_line.turns[1].black_move.is_none());
+
+    let nf6 = bc4_line.turns[0].black_move.as_ref().unwrap();
+    assert_eq!(nf6.full_str, "Nf6");
+    let bc5_line = &nf6.variations[0];
+    assert!(bc5_line.turns[0].white_move.is_none());
+    assert_eq!(
+        bc5_line.turns[0].black_move.as_ref().unwrap().full_str,
+        "Bc5"
+    );
+    assert_eq!(bc5_line.moves().len(), 2);
+
+    let nc6 = game.turns[1].black_move.as_ref().unwrap();
+    assert_eq!(nc6.variations[0].moves()[0].full_str, "d6");
+}
+
+#[test]
+fn test_variation_comments() {
+    let pgn_text = "1.e4 e5 ({classical is} 1...c5 {sicilian}) {back to main} 2.Nf3 1-0";
+    let game = parse_pgn(pgn_text).unwrap();
+    let e5 = game.turns[0].black_move.as_ref().unwrap();
+    assert_eq!(e5.comments, vec!["back to main"]);
+
+    let c5 = e5.variations[0].moves()[0];
+    assert_eq!(c5.pre_comments, vec!["classical is"]);
+    assert_eq!(c5.comments, vec!["sicilian"]);
+}
+
+#[test]
+fn test_promote_variation() {
+    let pgn_text = "1.e4 e5 2.Nf3 (2.Bc4 Nf6 3.d3) 2...Nc6 3.Bb5 1-0";
+    let mut game = parse_pgn(pgn_text).unwrap();
+
+    assert!(!game.promote_variation(2, 1));
+    assert!(!game.promote_variation(10, 0));
+    assert!(game.promote_variation(2, 0));
+
+    let mainline: Vec<&str> = game
+        .mainline()
+        .iter()
+        .map(|mv| mv.full_str.as_str())
+        .collect();
+    assert_eq!(mainline, vec!["e4", "e5", "Bc4", "Nf6", "d3"]);
+    assert_eq!(game.turns.len(), 3);
+    assert_eq!(game.turns[2].turn_number, 3);
+    assert_eq!(game.turns[2].black_move.as_ref().unwrap().full_str, "");
+
+    let bc4 = game.turns[1].white_move.as_ref().unwrap();
+    let demoted: Vec<&str> = bc4.variations[0]
+        .moves()
+        .iter()
+        .map(|mv| mv.full_str.as_str())
+        .collect();
+    assert_eq!(demoted, vec!["Nf3", "Nc6", "Bb5"]);
+    assert_eq!(bc4.variations[0].turns[0].turn_number, 2);
+
+    assert!(game.promote_variation(2, 0));
+    let mainline: Vec<&str> = game
+        .mainline()
+        .iter()
+        .map(|mv| mv.full_str.as_str())
+        .collect();
+    assert_eq!(mainline, vec!["e4", "e5", "Nf3", "Nc6", "Bb5"]);
+}
+
+#[test]
+fn test_invalid_variation() {
+    let data = "1.e4 e5 (1...c5 2.Nf3 1-0";
+
+    let result = parse_pgn(data);
+    assert!(result.is_err(), "Parser should fail on unclosed variation");
+}
+
+#[test]
+fn test_nags() {
+    let pgn_text = "1.e4 $1 e5 $2 $14 {comment} $36 2.Nf3 (2.Bc4 $5) $255 $0 1-0";
+    let game = parse_pgn(pgn_text).unwrap();
+
+    let e4 = game.turns[0].white_move.as_ref().unwrap();
+    assert_eq!(e4.full_str, "e4");
+    assert_eq!(e4.nags, vec![1]);
+
+    let e5 = game.turns[0].black_move.as_ref().unwrap();
+    assert_eq!(e5.nags, vec![2, 14, 36]);
+    assert_eq!(e5.comments, vec!["comment"]);
+
+    let nf3 = game.turns[1].white_move.as_ref().unwrap();
+    assert_eq!(nf3.nags, vec![255, 0]);
+    assert_eq!(nf3.variations[0].moves()[0].nags, vec![5]);
+}
+
+#[test]
+fn test_invalid_nag() {
+    assert!(parse_pgn("1.e4 $256 e5 1-0").is_err());
+    assert!(parse_pgn("1.e4 $ e5 1-0").is_err());
+    assert!(parse_pgn("1.e4 $01 e5 1-0").is_err());
+}
+
+#[test]
+fn test_nag_table() {
+    assert_eq!(nag_meaning(0), Some("null annotation"));
+    assert_eq!(nag_meaning(14), Some("White has a slight advantage"));
+    assert_eq!(nag_meaning(132), Some("White has moderate counterplay"));
+    assert_eq!(
+        nag_meaning(139),
+        Some("Black has severe time control pressure")
+    );
+    assert_eq!(nag_meaning(140), None);
+
+    assert_eq!(nag_from_suffix("!?"), Some(5));
+    assert_eq!(nag_from_suffix("+"), None);
+    assert_eq!(nag_to_suffix(4), Some("??"));
+    assert_eq!(nag_to_suffix(14), None);
+    for nag in 1..=6 {
+        assert_eq!(nag_from_suffix(nag_to_suffix(nag).unwrap()), Some(nag));
+    }
+}
+
+#[test]
+fn test_check_and_quality() {
+    let pgn_text = "1.Qxf7+! Rd8#!! 2.e4?+ Nf3 3.e5# e6!? 1-0";
+    let game = parse_pgn(pgn_text).unwrap();
+
+    let qxf7 = game.turns[0].white_move.as_ref().unwrap();
+    assert_eq!(qxf7.full_str, "Qxf7+!");
+    assert_eq!(qxf7.annotation, "+!");
+    assert!(qxf7.check);
+    assert!(!qxf7.mate);
+    assert_eq!(qxf7.quality, "!");
+
+    let rd8 = game.turns[0].black_move.as_ref().unwrap();
+    assert!(!rd8.check);
+    assert!(rd8.mate);
+    assert_eq!(rd8.quality, "!!");
+
+    let e4 = game.turns[1].white_move.as_ref().unwrap();
+    assert!(e4.check);
+    assert_eq!(e4.quality, "?");
+
+    let nf3 = game.turns[1].black_move.as_ref().unwrap();
+    assert!(!nf3.check);
+    assert!(!nf3.mate);
+    assert_eq!(nf3.quality, "");
+
+    let e5 = game.turns[2].white_move.as_ref().unwrap();
+    assert!(e5.mate);
+    assert_eq!(e5.quality, "");
+
+    let e6 = game.turns[2].black_move.as_ref().unwrap();
+    assert!(!e6.check);
+    assert_eq!(e6.quality, "!?");
+}
+
+#[test]
+fn test_invalid_double_check_mark() {
+    assert!(parse_pgn("1.Qxf7+# e5 1-0").is_err());
+    assert!(parse_pgn("1.Qxf7!+? e5 1-0").is_err());
+}
+
+#[test]
+fn test_black_continuation_numbers() {
+    let pgn_text = r#"
+    [SetUp "1"]
+    {black to move} 12... Nf6 13.e4 {long think} 13... e5 14. Nf3 14. ... Nc6 0-1
+    "#;
+    let game = parse_pgn(pgn_text).unwrap();
+    assert_eq!(game.turns.len(), 3);
+
+    assert_eq!(game.turns[0].turn_number, 12);
+    assert!(game.turns[0].white_move.is_none());
+    let nf6 = game.turns[0].black_move.as_ref().unwrap();
+    assert_eq!(nf6.full_str, "Nf6");
+    assert_eq!(nf6.pre_comments, vec!["black to move"]);
+
+    assert_eq!(game.turns[1].turn_number, 13);
+    let e4 = game.turns[1].white_move.as_ref().unwrap();
+    assert_eq!(e4.comments, vec!["long think"]);
+    assert_eq!(game.turns[1].black_move.as_ref().unwrap().full_str, "e5");
+
+    assert_eq!(game.turns[2].turn_number, 14);
+    assert_eq!(game.turns[2].black_move.as_ref().unwrap().full_str, "Nc6");
+
+    let mainline: Vec<&str> = game
+        .mainline()
+        .iter()
+        .map(|mv| mv.full_str.as_str())
+        .collect();
+    assert_eq!(mainline, vec!["Nf6", "e4", "e5", "Nf3", "Nc6"]);
+}
+
+#[test]
+fn test_black_only_game() {
+    let game = parse_pgn("30... Qxh2# 0-1").unwrap();
+    assert_eq!(game.turns.len(), 1);
+    assert_eq!(game.turns[0].turn_number, 30);
+    assert!(game.turns[0].white_move.is_none());
+    assert!(game.turns[0].black_move.as_ref().unwrap().mate);
+}
+
+#[test]
+fn test_invalid_black_continuation() {
+    assert!(parse_pgn("1.e4 e5 2... Nf3 1-0").is_err());
+    assert!(parse_pgn("1... e5 e4 1-0").is_err());
+}
+
+#[test]
+fn test_mismatched_black_continuation() {
+    let options = ParseOptions {
+        validate_moves: true,
+        ..Default::default()
+    };
+    let error = parse_pgn_with_options("1.e4 5...e5 6.Nf3 1-0", &options).unwrap_err();
+    assert!(matches!(
+        error,
+        PgnError::Syntax { line: 1, column: 6, ref token, ref expected }
+            if token == "5..." && expected == "\"1...\""
+    ));
+    assert!(parse_pgn("1.e4 e5 1...d5 1-0").is_err());
+    assert!(parse_pgn("1.e4 e5 2.Nf3 (2.Bc4 3...Nf6) 2...Nc6 1-0").is_err());
+
+    // a variation may still open with black to move
+    assert!(parse_pgn("1.e4 e5 2.Nf3 Nc6 (2...d6 3.d4) 3.Bb5 1-0").is_ok());
+}
+
+#[test]
+fn test_unknown_result() {
+    let game = parse_pgn("[Event \"Ongoing\"] 1.e4 e5 2.Nf3 *").unwrap();
+    assert_eq!(game.result, GameResult::Unknown);
+    assert_eq!(game.turns.len(), 2);
+    assert_eq!(game.result.to_string(), "*");
+
+    let games = parse_pgn_database("1.e4 * 1.d4 d5 1-0");
+    assert_eq!(games.len(), 2);
+    assert_eq!(games[0].as_ref().unwrap().result, GameResult::Unknown);
+    assert_eq!(games[1].as_ref().unwrap().result, GameResult::WhiteWins);
+}
+
+#[test]
+fn test_game_result_conversion() {
+    for result in ["1-0", "0-1", "1/2-1/2", "*"] {
+        assert_eq!(result.parse::<GameResult>().unwrap().to_string(), result);
+    }
+    assert!("1-1".parse::<GameResult>().is_err());
+}
+
+#[test]
+fn test_fragment_without_result() {
+    let options = ParseOptions {
+        require_result: false,
+        ..Default::default()
+    };
+
+    let game = parse_pgn_with_options("1.e4 e5 2.Nf3 {main line}", &options).unwrap();
+    assert_eq!(game.result, GameResult::Unknown);
+    assert_eq!(game.turns.len(), 2);
+    assert_eq!(
+        game.turns[1].white_move.as_ref().unwrap().comments,
+        vec!["main line"]
+    );
+
+    let game = parse_pgn_with_options("5... Nf6 6.e5", &options).unwrap();
+    assert!(game.turns[0].white_move.is_none());
+    assert_eq!(game.turns.len(), 2);
+
+    let game = parse_pgn_with_options("1.e4 e5 0-1", &options).unwrap();
+    assert_eq!(game.result, GameResult::BlackWins);
+
+    assert!(parse_pgn_with_options("1.e4 e5 2.Nf3 junk", &options).is_err());
+    assert!(parse_pgn("1.e4 e5 2.Nf3").is_err());
+}
+
+#[test]
+fn test_unplayed_black_move() {
+    let game = parse_pgn("1.e4 e5 2.Nf3 1-0").unwrap();
+    assert!(game.turns[0].black_move.as_ref().unwrap().is_played());
+    assert!(!game.turns[1].black_move.as_ref().unwrap().is_played());
+    assert_eq!(game.mainline().len(), 3);
+}
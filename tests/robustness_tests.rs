@@ -0,0 +1,199 @@
+use chess_parser::{
+    parse_pgn, parse_pgn_database, parse_pgn_lenient_with_options, parse_pgn_with_options,
+    MoveNotation, ParseOptions, PgnError, PgnReader, PieceLetters, MAX_VARIATION_DEPTH,
+};
+use std::io::Cursor;
+
+// small deterministic generator, so failures can be reproduced from the seed
+struct XorShift(u64);
+
+impl XorShift {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn below(&mut self, limit: usize) -> usize {
+        (self.next() % limit as u64) as usize
+    }
+}
+
+// pieces of PGN that random inputs are built from
+const FRAGMENTS: [&str; 40] = [
+    "1.",
+    "12...",
+    "99999999999999999999999.",
+    "3. ...",
+    "e4",
+    "exd5",
+    "Nf3",
+    "Nbd2",
+    "R1e1",
+    "Qh4xe1",
+    "e8=Q",
+    "O-O",
+    "O-O-O",
+    "+",
+    "#",
+    "!",
+    "?!",
+    "$1",
+    "$255",
+    "$256",
+    "{",
+    "}",
+    "{comment}",
+    ";",
+    "\n",
+    "(",
+    ")",
+    "[",
+    "]",
+    "\"",
+    "[Event \"x\"]",
+    "[FEN \"8/8/8/8/8/8/8/8 w - - 0 1\"]",
+    "[SetUp \"1\"]",
+    "1-0",
+    "0-1",
+    "1/2-1/2",
+    "*",
+    " ",
+    "é",
+    "\u{0}",
+];
+
+fn all_options() -> Vec<ParseOptions> {
+    let mut options = Vec::new();
+    for require_result in [true, false] {
+        for validate_moves in [true, false] {
+            for notation in [None, Some(MoveNotation::Long), Some(MoveNotation::Uci)] {
+                options.push(ParseOptions {
+                    require_result,
+                    validate_moves,
+                    notation,
+                    piece_letters: PieceLetters::ENGLISH,
+                });
+            }
+        }
+    }
+    options
+}
+
+// runs every entry point on the text - any panic fails the test
+fn parse_everywhere(text: &str) {
+    for options in all_options() {
+        let _ = parse_pgn_with_options(text, &options);
+        for game in PgnReader::with_options(Cursor::new(text.as_bytes()), options) {
+            let _ = game;
+        }
+    }
+    let _ = parse_pgn_database(text);
+
+    let lenient = ParseOptions {
+        require_result: true,
+        validate_moves: true,
+        notation: None,
+        piece_letters: PieceLetters::GERMAN,
+    };
+    let _ = parse_pgn_lenient_with_options(text, &lenient);
+}
+
+#[test]
+fn test_huge_turn_number() {
+    let error = parse_pgn("99999999999999999999999. e4 e5 1-0").unwrap_err();
+    assert!(matches!(
+        error,
+        PgnError::Syntax {
+            line: 1,
+            column: 1,
+            ..
+        }
+    ));
+    assert_eq!(error.token(), Some("99999999999999999999999."));
+
+    let error = parse_pgn("1.e4 99999999999999999999999... e5 1-0").unwrap_err();
+    assert!(matches!(error, PgnError::Syntax { .. }));
+}
+
+#[test]
+fn test_deep_variations() {
+    let nested =
+        |depth: usize| format!("1.e4 {}e5{} 1-0", "(1.d4 ".repeat(depth), ")".repeat(depth));
+    assert!(parse_pgn(&nested(MAX_VARIATION_DEPTH)).is_ok());
+
+    let error = parse_pgn(&nested(MAX_VARIATION_DEPTH + 1)).unwrap_err();
+    assert!(matches!(error, PgnError::Syntax { .. }));
+    assert_eq!(error.token(), Some("("));
+
+    // unbalanced input is rejected the same way
+    assert!(parse_pgn(&"(".repeat(100_000)).is_err());
+
+    // parentheses in comments are just text
+    let comment = format!("1.e4 {{{}}} e5 1-0", "(".repeat(1000));
+    assert!(parse_pgn(&comment).is_ok());
+}
+
+#[test]
+fn test_unterminated_input() {
+    for text in [
+        "{",
+        "1.e4 {",
+        "[Event \"",
+        "1.e4 ;",
+        "1.e4 (",
+        "$",
+        "1.e4 $",
+        "1...",
+        "é",
+    ] {
+        assert!(parse_pgn(text).is_err(), "{:?} should be rejected", text);
+        parse_everywhere(text);
+    }
+}
+
+#[test]
+fn test_random_fragments_never_panic() {
+    let mut rng = XorShift(0x2545_f491_4f6c_dd1d);
+    for _ in 0..2000 {
+        let length = rng.below(30);
+        let text: String = (0..length)
+            .map(|_| FRAGMENTS[rng.below(FRAGMENTS.len())])
+            .collect::<Vec<_>>()
+            .join(if rng.below(2) == 0 { " " } else { "" });
+        parse_everywhere(&text);
+    }
+}
+
+#[test]
+fn test_mutated_games_never_panic() {
+    let games = [
+        include_str!("../examples/test.chess"),
+        include_str!("../examples/comments.chess"),
+        include_str!("../examples/topalov_kasparov.chess"),
+    ];
+    let mut rng = XorShift(0x9e37_79b9_7f4a_7c15);
+
+    for game in games {
+        let chars: Vec<char> = game.chars().collect();
+        for _ in 0..200 {
+            let mut mutated = chars.clone();
+            for _ in 0..=rng.below(4) {
+                let index = rng.below(mutated.len() + 1);
+                match rng.below(4) {
+                    0 if index < mutated.len() => {
+                        mutated.remove(index);
+                    }
+                    1 => mutated.truncate(index),
+                    _ => {
+                        let fragment = FRAGMENTS[rng.below(FRAGMENTS.len())];
+                        let at = index.min(mutated.len());
+                        mutated.splice(at..at, fragment.chars());
+                    }
+                }
+            }
+            parse_everywhere(&mutated.into_iter().collect::<String>());
+        }
+    }
+}
@@ -0,0 +1,86 @@
+use chess_parser::{parse_pgn, Board, ReplayErrorKind};
+
+// SAN the board generates for the move given in coordinates, e.g. "g1f3"
+fn san(fen: &str, from: &str, to: &str) -> String {
+    let board = Board::from_fen(fen).unwrap();
+    let moves = board.legal_moves();
+    let mv = moves
+        .iter()
+        .find(|mv| {
+            mv.from.to_string() == from
+                && mv.to.to_string() == to
+                && mv.promotion.map_or(true, |p| p.letter() == 'Q')
+        })
+        .unwrap();
+    board.san(mv)
+}
+
+#[test]
+fn test_san_of_plain_moves() {
+    let start = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+    assert_eq!(san(start, "g1", "f3"), "Nf3");
+    assert_eq!(san(start, "e2", "e4"), "e4");
+
+    let fen = "rnbqkbnr/ppp1pppp/8/3p4/4P3/8/PPPP1PPP/RNBQKBNR w KQkq - 0 2";
+    assert_eq!(san(fen, "e4", "d5"), "exd5");
+
+    // en passant
+    let fen = "4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1";
+    assert_eq!(san(fen, "e5", "d6"), "exd6");
+
+    // promotion with check, and castling
+    let fen = "4k3/1P6/8/8/8/8/8/R3K2R w KQ - 0 1";
+    assert_eq!(san(fen, "b7", "b8"), "b8=Q+");
+    assert_eq!(san(fen, "e1", "g1"), "O-O");
+    assert_eq!(san(fen, "e1", "c1"), "O-O-O");
+    assert_eq!(san(fen, "a1", "a8"), "Ra8+");
+
+    // fool's mate
+    let fen = "rnbqkbnr/pppp1ppp/8/4p3/6P1/5P2/PPPPP2P/RNBQKBNR b KQkq g3 0 2";
+    assert_eq!(san(fen, "d8", "h4"), "Qh4#");
+}
+
+#[test]
+fn test_san_disambiguation() {
+    // knights on b1 and f3 both reach d2
+    let fen = "4k3/8/8/8/8/5N2/8/1N2K3 w - - 0 1";
+    assert_eq!(san(fen, "b1", "d2"), "Nbd2");
+    // only one of them reaches a3
+    assert_eq!(san(fen, "b1", "a3"), "Na3");
+
+    // rooks on the same file
+    let fen = "k7/8/8/8/4R3/8/8/4R2K w - - 0 1";
+    assert_eq!(san(fen, "e1", "e2"), "R1e2");
+
+    // queens sharing both the file and the rank with others
+    let fen = "1k6/8/8/8/4Q2Q/8/8/K6Q w - - 0 1";
+    assert_eq!(san(fen, "h4", "e1"), "Qh4e1");
+    assert_eq!(san(fen, "e4", "e1"), "Qee1");
+}
+
+#[test]
+fn test_normalize_san() {
+    let pgn_text = "1.Pe4 e5 2.Ng1f3 {develops} Nb8c6 (2...Ng8f6!? 3.Nf3xe5) \
+                    3.Bf1c4 Ng8f6 4.Nb1c3 Bf8c5 5.O-O O-O 6.Rf1e1 d6 7.Nc3d5+ Nf6xd5 *";
+    let mut game = parse_pgn(pgn_text).unwrap();
+    assert_eq!(game.normalize_san().unwrap(), 12);
+    assert_eq!(
+        game.to_pgn(),
+        "1. e4 e5 2. Nf3 {develops} 2... Nc6 (2... Nf6!? 3. Nxe5) 3. Bc4 Nf6 4. Nc3 Bc5\n\
+         5. O-O O-O 6. Re1 d6 7. Nd5 Nxd5 *\n"
+    );
+
+    let knight = game.turns[1].white_move.as_ref().unwrap();
+    assert_eq!(knight.disambig, None);
+    assert_eq!(knight.comments, ["develops"]);
+    assert_eq!((knight.line, knight.column), (1, 12));
+
+    // already canonical
+    assert_eq!(game.normalize_san().unwrap(), 0);
+
+    let mut game = parse_pgn("1.e4 e5 2.Ke3 *").unwrap();
+    assert_eq!(
+        game.normalize_san().unwrap_err().kind,
+        ReplayErrorKind::Illegal
+    );
+}
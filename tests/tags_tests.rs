@@ -0,0 +1,88 @@
+use chess_parser::{parse_pgn, GameResult, Tags, SEVEN_TAG_ROSTER};
+
+#[test]
+fn test_tags_keep_source_order() {
+    let pgn_text = r#"[White "A"]
+[ECO "C60"]
+[Event "E"]
+[Black "B"]
+
+1.e4 e5 *"#;
+    let game = parse_pgn(pgn_text).unwrap();
+    let keys: Vec<&str> = game.metadata.iter().map(|(key, _)| key).collect();
+    assert_eq!(keys, ["White", "ECO", "Event", "Black"]);
+    assert_eq!(game.metadata.len(), 4);
+    assert_eq!(game.metadata["ECO"], "C60");
+    assert_eq!(game.metadata.get("Site"), None);
+    assert!(game.metadata.duplicates().is_empty());
+}
+
+#[test]
+fn test_seven_tag_roster_accessors() {
+    let pgn_text = r#"[Event "F/S Return Match"]
+[Site "Belgrade, Serbia JUG"]
+[Date "1992.11.04"]
+[Round "29"]
+[White "Fischer, Robert J."]
+[Black "Spassky, Boris V."]
+[Result "1/2-1/2"]
+
+1.e4 e5 1/2-1/2"#;
+    let tags = parse_pgn(pgn_text).unwrap().metadata;
+    assert_eq!(tags.event(), Some("F/S Return Match"));
+    assert_eq!(tags.site(), Some("Belgrade, Serbia JUG"));
+    assert_eq!(tags.date(), Some("1992.11.04"));
+    assert_eq!(tags.round(), Some("29"));
+    assert_eq!(tags.white(), Some("Fischer, Robert J."));
+    assert_eq!(tags.black(), Some("Spassky, Boris V."));
+    assert_eq!(tags.result(), Some(GameResult::Draw));
+    assert!(SEVEN_TAG_ROSTER.iter().all(|key| tags.contains_key(key)));
+
+    let tags: Tags = [("Result", "2-0")].into_iter().collect();
+    assert_eq!(tags.result(), None);
+    assert_eq!(tags.event(), None);
+}
+
+#[test]
+fn test_duplicate_tags() {
+    let pgn_text = r#"[Event "First"]
+[White "A"]
+[Event "Second"]
+[White "A"]
+[Event "Third"]
+
+1.e4 *"#;
+    let game = parse_pgn(pgn_text).unwrap();
+    assert_eq!(game.metadata.len(), 5);
+    assert_eq!(game.metadata.event(), Some("First"));
+    let events: Vec<&str> = game.metadata.get_all("Event").collect();
+    assert_eq!(events, ["First", "Second", "Third"]);
+    assert_eq!(game.metadata.duplicates(), ["Event", "White"]);
+
+    // repeated tags are written back out, after each other
+    let written = parse_pgn(&game.to_pgn()).unwrap().metadata;
+    assert_eq!(written.get_all("Event").collect::<Vec<_>>(), events);
+    assert_eq!(written.duplicates(), ["Event", "White"]);
+}
+
+#[test]
+fn test_edit_tags() {
+    let mut tags: Tags = [("Event", "A"), ("Site", "S"), ("Event", "B")]
+        .into_iter()
+        .collect();
+    tags.set("Event", "C");
+    assert_eq!(
+        tags.iter().collect::<Vec<_>>(),
+        [("Event", "C"), ("Site", "S")]
+    );
+    tags.set("Round", "1");
+    assert_eq!(tags.get("Round"), Some("1"));
+    assert_eq!(tags.len(), 3);
+
+    tags.push("Site", "T");
+    assert_eq!(tags.remove("Site"), Some("S".to_string()));
+    assert!(!tags.contains_key("Site"));
+    assert_eq!(tags.remove("Site"), None);
+    assert_eq!(tags.len(), 2);
+    assert!(!tags.is_empty());
+}
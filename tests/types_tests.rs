@@ -0,0 +1,76 @@
+use chess_parser::{parse_pgn, Color, Disambiguation, File, Piece, Rank, Square};
+
+#[test]
+fn test_piece_letters() {
+    for piece in [
+        Piece::Pawn,
+        Piece::Knight,
+        Piece::Bishop,
+        Piece::Rook,
+        Piece::Queen,
+        Piece::King,
+    ] {
+        assert_eq!(piece.to_string().parse::<Piece>(), Ok(piece));
+    }
+    assert_eq!(Piece::Knight.to_string(), "N");
+    assert_eq!(Piece::from_letter('P'), Some(Piece::Pawn));
+    assert_eq!(Piece::from_letter('n'), None);
+    assert!("X".parse::<Piece>().is_err());
+    assert!("NN".parse::<Piece>().is_err());
+}
+
+#[test]
+fn test_squares() {
+    let square: Square = "e4".parse().unwrap();
+    assert_eq!(square.file, File::from_char('e').unwrap());
+    assert_eq!(square.rank, Rank::from_char('4').unwrap());
+    assert_eq!((square.file.index(), square.rank.index()), (4, 3));
+    assert_eq!(square.to_string(), "e4");
+    assert_eq!(Square::from_indices(0, 7).unwrap().to_string(), "a8");
+    assert_eq!(Square::from_indices(8, 0), None);
+    assert_eq!(Square::all().count(), 64);
+
+    for text in ["", "e", "i4", "e9", "e44", "E4"] {
+        assert!(
+            text.parse::<Square>().is_err(),
+            "{:?} should be rejected",
+            text
+        );
+    }
+    assert_eq!("h".parse::<File>().map(File::index), Ok(7));
+    assert_eq!("1".parse::<Rank>().map(Rank::index), Ok(0));
+}
+
+#[test]
+fn test_colors() {
+    assert_eq!("white".parse(), Ok(Color::White));
+    assert_eq!("b".parse(), Ok(Color::Black));
+    assert!("red".parse::<Color>().is_err());
+    assert_eq!(Color::Black.to_string(), "black");
+    assert_eq!(Color::White.opponent(), Color::Black);
+}
+
+#[test]
+fn test_typed_move_fields() {
+    let game = parse_pgn("1.O-O exd8=N 1-0").unwrap();
+    let castle = game.turns[0].white_move.as_ref().unwrap();
+    assert_eq!(castle.piece, Piece::King);
+    assert_eq!(castle.target(), None);
+    assert_eq!(castle.promotion, None);
+
+    let capture = game.turns[0].black_move.as_ref().unwrap();
+    assert_eq!(capture.piece, Piece::Pawn);
+    assert_eq!(capture.target(), "d8".parse().ok());
+    assert_eq!(capture.promotion, Some(Piece::Knight));
+}
+
+#[test]
+fn test_disambiguation() {
+    for text in ["b", "1", "b5"] {
+        let disambig: Disambiguation = text.parse().unwrap();
+        assert_eq!(disambig.to_string(), text);
+    }
+    assert!(matches!("h".parse(), Ok(Disambiguation::File(_))));
+    assert!(matches!("8".parse(), Ok(Disambiguation::Rank(_))));
+    assert!("x".parse::<Disambiguation>().is_err());
+}
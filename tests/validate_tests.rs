@@ -0,0 +1,170 @@
+use chess_parser::{parse_pgn, CheckMark, CheckMarkIssue, Color, GameResult, ResultIssue};
+
+#[test]
+fn test_consistent_result() {
+    let game = parse_pgn(include_str!("../examples/fisher_spassky.chess")).unwrap();
+    assert!(game.check_result().is_empty());
+
+    let game = parse_pgn("[Result \"*\"]\n1.e4 e5 *").unwrap();
+    assert!(game.check_result().is_empty());
+}
+
+#[test]
+fn test_result_tag_problems() {
+    let game = parse_pgn("[Result \"1-0\"]\n1.e4 e5 0-1").unwrap();
+    let issues = game.check_result();
+    assert_eq!(
+        issues,
+        [ResultIssue::Mismatch {
+            tag: GameResult::WhiteWins,
+            movetext: GameResult::BlackWins,
+        }]
+    );
+    assert_eq!(
+        issues[0].to_string(),
+        "Result tag '1-0' doesn't match the movetext result '0-1'"
+    );
+
+    let game = parse_pgn("[Event \"E\"]\n1.e4 e5 1-0").unwrap();
+    assert_eq!(game.check_result(), [ResultIssue::MissingTag]);
+
+    let game = parse_pgn("[Result \"2-0\"]\n1.e4 e5 1-0").unwrap();
+    let issues = game.check_result();
+    assert_eq!(issues, [ResultIssue::InvalidTag("2-0".to_string())]);
+    assert_eq!(issues[0].to_string(), "invalid Result tag '2-0'");
+}
+
+#[test]
+fn test_result_contradicting_checkmate() {
+    // fool's mate, won by black
+    let game = parse_pgn("[Result \"1-0\"]\n1.f3 e5 2.g4 Qh4# 1-0").unwrap();
+    let issues = game.check_result();
+    assert_eq!(
+        issues,
+        [ResultIssue::ContradictsCheckmate {
+            winner: Color::Black,
+            declared: GameResult::WhiteWins,
+        }]
+    );
+    assert_eq!(
+        issues[0].to_string(),
+        "result '1-0' contradicts the checkmate by black"
+    );
+
+    let game = parse_pgn("[Result \"1/2-1/2\"]\n1.f3 e5 2.g4 Qh4# *").unwrap();
+    assert_eq!(
+        game.check_result(),
+        [
+            ResultIssue::Mismatch {
+                tag: GameResult::Draw,
+                movetext: GameResult::Unknown,
+            },
+            ResultIssue::ContradictsCheckmate {
+                winner: Color::Black,
+                declared: GameResult::Draw,
+            },
+        ]
+    );
+
+    let game = parse_pgn("[Result \"0-1\"]\n1.f3 e5 2.g4 Qh4# 0-1").unwrap();
+    assert!(game.check_result().is_empty());
+}
+
+#[test]
+fn test_result_without_board() {
+    // the moves can't be replayed, so only the tags are checked
+    let game = parse_pgn("[Result \"1-0\"]\n1.e5 e4 1/2-1/2").unwrap();
+    assert_eq!(
+        game.check_result(),
+        [ResultIssue::Mismatch {
+            tag: GameResult::WhiteWins,
+            movetext: GameResult::Draw,
+        }]
+    );
+}
+
+#[test]
+fn test_verify_check_marks() {
+    let game = parse_pgn(include_str!("../examples/topalov_kasparov.chess")).unwrap();
+    assert!(game.verify_check_marks().unwrap().is_empty());
+
+    // Bb5 gives check, Nc3 doesn't
+    let game = parse_pgn("1.e4 d5 2.Bb5 c6 3.Nc3+ *").unwrap();
+    let issues = game.verify_check_marks().unwrap();
+    assert_eq!(
+        issues,
+        [
+            CheckMarkIssue {
+                turn_number: 2,
+                color: Color::White,
+                move_text: "Bb5".to_string(),
+                written: None,
+                actual: Some(CheckMark::Check),
+                line: 1,
+                column: 11,
+            },
+            CheckMarkIssue {
+                turn_number: 3,
+                color: Color::White,
+                move_text: "Nc3+".to_string(),
+                written: Some(CheckMark::Check),
+                actual: None,
+                line: 1,
+                column: 20,
+            },
+        ]
+    );
+    assert_eq!(
+        issues[0].to_string(),
+        "missing '+' on 'Bb5' in turn 2 (white) at line 1, column 11"
+    );
+    assert_eq!(
+        issues[1].to_string(),
+        "spurious '+' on 'Nc3+' in turn 3 (white) at line 1, column 20"
+    );
+
+    // fool's mate, written as a check
+    let game = parse_pgn("1.f3 e5 2.g4 Qh4+! 0-1").unwrap();
+    let issues = game.verify_check_marks().unwrap();
+    assert_eq!(issues.len(), 1);
+    assert_eq!(issues[0].written, Some(CheckMark::Check));
+    assert_eq!(issues[0].actual, Some(CheckMark::Mate));
+    assert_eq!(
+        issues[0].to_string(),
+        "'+' instead of '#' on 'Qh4+!' in turn 2 (black) at line 1, column 14"
+    );
+
+    // the moves have to be replayable
+    let game = parse_pgn("1.e4 e4 *").unwrap();
+    assert!(game.verify_check_marks().is_err());
+}
+
+#[test]
+fn test_normalize_check_marks() {
+    let mut game = parse_pgn("1.e4 d5 2.Bb5 c6 3.Nc3+ *").unwrap();
+    assert_eq!(game.normalize_check_marks().unwrap(), 2);
+    assert!(game.verify_check_marks().unwrap().is_empty());
+    assert_eq!(game.to_pgn(), "1. e4 d5 2. Bb5+ c6 3. Nc3 *\n");
+
+    let mut game = parse_pgn("1.f3 e5 2.g4 Qh4+! 0-1").unwrap();
+    assert_eq!(game.normalize_check_marks().unwrap(), 1);
+    let mate = game.turns[1].black_move.as_ref().unwrap();
+    assert_eq!(mate.full_str, "Qh4#!");
+    assert!(mate.mate && !mate.check);
+    assert_eq!(mate.annotation, "#!");
+    assert_eq!(mate.quality, "!");
+    assert_eq!(mate.check_mark(), Some(CheckMark::Mate));
+
+    assert_eq!(game.normalize_check_marks().unwrap(), 0);
+}
+
+#[test]
+fn test_set_check_mark_on_edited_move() {
+    let mut game = parse_pgn("1.e4 e5 2.Qh5 Nc6 3.Bc4 Nf6 4.Qxf7# 1-0").unwrap();
+    let mate = game.turns[3].white_move.as_mut().unwrap();
+    // the move text no longer ends with its annotation
+    mate.full_str = "Q".to_string();
+    mate.set_check_mark(Some(CheckMark::Check));
+    assert_eq!(mate.full_str, "Q+");
+    assert!(mate.check && !mate.mate);
+}
@@ -0,0 +1,185 @@
+use chess_parser::{parse_pgn, parse_pgn_database, ChessGame, ChessMove, ChessTurn};
+
+// everything the model stores except source positions, which change when the text is rewritten
+fn assert_same_moves(a: &ChessMove, b: &ChessMove) {
+    assert_eq!(a.full_str, b.full_str);
+    assert_eq!(a.castling, b.castling);
+    assert_eq!(a.capture, b.capture);
+    assert_eq!(a.piece, b.piece);
+    assert_eq!(a.promotion, b.promotion);
+    assert_eq!(a.target(), b.target());
+    assert_eq!(a.annotation, b.annotation);
+    assert_eq!(a.disambig, b.disambig);
+    assert_eq!(a.comments, b.comments);
+    assert_eq!(a.pre_comments, b.pre_comments);
+    assert_eq!(a.nags, b.nags);
+    assert_eq!(a.variations.len(), b.variations.len());
+    for (a, b) in a.variations.iter().zip(&b.variations) {
+        assert_same_turns(&a.turns, &b.turns);
+        assert_eq!(a.comments, b.comments);
+    }
+}
+
+fn assert_same_turns(a: &[ChessTurn], b: &[ChessTurn]) {
+    assert_eq!(a.len(), b.len());
+    for (a, b) in a.iter().zip(b) {
+        assert_eq!(a.turn_number, b.turn_number);
+        for (a, b) in [
+            (&a.white_move, &b.white_move),
+            (&a.black_move, &b.black_move),
+        ] {
+            assert_eq!(a.is_some(), b.is_some());
+            if let (Some(a), Some(b)) = (a, b) {
+                assert_same_moves(a, b);
+            }
+        }
+    }
+}
+
+fn assert_round_trip(game: &ChessGame) {
+    let written = game.to_pgn();
+    let reparsed = parse_pgn(&written).unwrap_or_else(|error| panic!("{}\n{}", error, written));
+    assert_eq!(reparsed.metadata, game.metadata);
+    assert_eq!(reparsed.result, game.result);
+    assert_same_turns(&reparsed.turns, &game.turns);
+    assert_eq!(reparsed.comments, game.comments);
+    assert_eq!(reparsed.to_pgn(), written);
+}
+
+#[test]
+fn test_to_pgn_export_format() {
+    // the example is already in export format
+    let pgn_text = include_str!("../examples/topalov_kasparov.chess");
+    let game = parse_pgn(pgn_text).unwrap();
+    assert_eq!(game.to_pgn(), format!("{}\n", pgn_text.trim_end()));
+}
+
+#[test]
+fn test_to_pgn_tag_order() {
+    let pgn_text = r#"[Result "*"]
+[Opening "Sicilian"]
+[White "A"]
+[Black "B"]
+[ECO "B20"]
+[Event "E"]
+
+1.e4 c5 *"#;
+    let game = parse_pgn(pgn_text).unwrap();
+    assert_eq!(
+        game.to_pgn(),
+        r#"[Event "E"]
+[White "A"]
+[Black "B"]
+[Result "*"]
+[Opening "Sicilian"]
+[ECO "B20"]
+
+1. e4 c5 *
+"#
+    );
+
+    // no tag section at all without tags
+    assert_eq!(parse_pgn("1.e4 *").unwrap().to_pgn(), "1. e4 *\n");
+    assert_eq!(ChessGame::default().to_pgn(), "*\n");
+}
+
+#[test]
+fn test_to_pgn_escapes_tag_values() {
+    let pgn_text = r#"[Event "The \"Immortal\" Game"]
+[Site "C:\games\\"]
+
+1.e4 *"#;
+    let game = parse_pgn(pgn_text).unwrap();
+    assert!(game
+        .to_pgn()
+        .starts_with("[Event \"The \\\"Immortal\\\" Game\"]\n[Site \"C:\\\\games\\\\\"]\n"));
+    assert_round_trip(&game);
+}
+
+#[test]
+fn test_to_pgn_comments_and_variations() {
+    let pgn_text = "{start} 1.e4 $1 {a} (1.d4 d5 (1...Nf6 2.c4 ; x}y\n) 2.c4) (1.c4) e5 2.Nf3 *";
+    let game = parse_pgn(pgn_text).unwrap();
+    assert_eq!(
+        game.to_pgn(),
+        "1. {start} e4 $1 {a} (1. d4 d5 (1... Nf6 2. c4 ;x}y\n) 2. c4) (1. c4) 1... e5 2. Nf3 *\n"
+    );
+    assert_round_trip(&game);
+}
+
+#[test]
+fn test_to_pgn_comments_without_moves() {
+    let game = parse_pgn("{only} {two} 1-0").unwrap();
+    assert!(game.turns.is_empty());
+    assert_eq!(game.comments, ["only", "two"]);
+    assert_eq!(game.to_pgn(), "{only} {two} 1-0\n");
+    assert_round_trip(&game);
+
+    let game = parse_pgn("1.e4 ({c}) e5 *").unwrap();
+    let e4 = game.turns[0].white_move.as_ref().unwrap();
+    assert_eq!(e4.variations[0].comments, ["c"]);
+    assert_eq!(game.to_pgn(), "1. e4 ({c}) 1... e5 *\n");
+    assert_round_trip(&game);
+}
+
+#[test]
+fn test_to_pgn_black_to_move() {
+    let pgn_text =
+        "[SetUp \"1\"]\n[FEN \"4k3/8/8/8/8/8/8/R3K3 b Q - 0 30\"]\n\n30... Kd7 31.O-O-O+ Kc6 1-0";
+    let game = parse_pgn(pgn_text).unwrap();
+    assert!(game.to_pgn().ends_with("\n30... Kd7 31. O-O-O+ Kc6 1-0\n"));
+    assert_round_trip(&game);
+
+    // a game ending after white's move
+    let game = parse_pgn("1.e4 e5 2.Nf3 *").unwrap();
+    assert_eq!(game.to_pgn(), "1. e4 e5 2. Nf3 *\n");
+    assert_round_trip(&game);
+}
+
+#[test]
+fn test_to_pgn_wraps_lines() {
+    let mut pgn_text = String::new();
+    for turn in 1..=40 {
+        pgn_text.push_str(&format!(
+            "{}.Nf3 {{a somewhat long comment}} (1.e4 $14 (1.d4)) Nf6 ",
+            turn
+        ));
+    }
+    pgn_text.push_str("1/2-1/2");
+    let game = parse_pgn(&pgn_text).unwrap();
+    let written = game.to_pgn();
+    assert!(written.lines().count() > 30);
+    for line in written.lines() {
+        assert!(line.len() <= 79, "line too long: {:?}", line);
+        assert_eq!(line, line.trim());
+    }
+    assert_round_trip(&game);
+}
+
+#[test]
+fn test_to_pgn_keeps_long_comments_whole() {
+    let comment = "a very long comment ".repeat(10).trim_end().to_string();
+    let pgn_text = format!("1.e4 {{{}}} e5 {{kept\nas written}} *", comment);
+    let game = parse_pgn(&pgn_text).unwrap();
+    let written = game.to_pgn();
+    assert!(written.contains(&format!("{{{}}}", comment)));
+    assert!(written.contains("{kept\nas written}"));
+    assert_round_trip(&game);
+}
+
+#[test]
+fn test_to_pgn_round_trip_examples() {
+    let examples = [
+        include_str!("../examples/test.chess"),
+        include_str!("../examples/comments.chess"),
+        include_str!("../examples/fisher_spassky.chess"),
+        include_str!("../examples/topalov_kasparov.chess"),
+    ];
+    for pgn_text in examples {
+        assert_round_trip(&parse_pgn(pgn_text).unwrap());
+    }
+    let database = parse_pgn_database(include_str!("../examples/database.chess"));
+    for game in database.into_iter().flatten() {
+        assert_round_trip(&game);
+    }
+}